@@ -0,0 +1,114 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime API definition for the stable asset pallet.
+//!
+//! Every method maps onto the method of the same purpose on
+//! `nutsfinance_stable_asset::traits::StableAsset`, so a runtime can implement the API by
+//! forwarding each call to the pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use nutsfinance_stable_asset::{
+	MintResult, PoolTokenIndex, RedeemMultiResult, RedeemProportionResult, RedeemSingleResult, StableAssetPoolId,
+	SwapResult, SwapRouteStep,
+};
+use parity_scale_codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait StableAssetApi<AssetId, Balance, AtLeast64BitUnsigned>
+	where
+		AssetId: Codec,
+		Balance: Codec,
+		AtLeast64BitUnsigned: Codec,
+	{
+		/// Get the swap result at exact input amount.
+		fn get_swap_output_amount(
+			pool_id: StableAssetPoolId,
+			input_index: PoolTokenIndex,
+			output_index: PoolTokenIndex,
+			dx: Balance,
+		) -> Option<SwapResult<Balance>>;
+
+		/// Get the swap result at exact output amount.
+		fn get_swap_input_amount(
+			pool_id: StableAssetPoolId,
+			input_index: PoolTokenIndex,
+			output_index: PoolTokenIndex,
+			dy: Balance,
+		) -> Option<SwapResult<Balance>>;
+
+		/// Get the pool and asset indexes giving the largest output amount, along with that amount.
+		fn get_best_route(
+			input_asset: AssetId,
+			output_asset: AssetId,
+			input_amount: Balance,
+		) -> Option<(StableAssetPoolId, PoolTokenIndex, PoolTokenIndex, Balance)>;
+
+		/// Get the mint result at exact input amounts.
+		fn get_mint_amount(pool_id: StableAssetPoolId, amounts: Vec<Balance>) -> Option<MintResult<Balance>>;
+
+		/// Get the proportional redeem result at exact pool token amount.
+		fn get_redeem_proportion_amount(
+			pool_id: StableAssetPoolId,
+			amount: Balance,
+		) -> Option<RedeemProportionResult<Balance>>;
+
+		/// Get the single asset redeem result at exact pool token amount.
+		fn get_redeem_single_amount(
+			pool_id: StableAssetPoolId,
+			amount: Balance,
+			output_index: PoolTokenIndex,
+		) -> Option<RedeemSingleResult<Balance>>;
+
+		/// Get the multi asset redeem result at exact output amounts.
+		fn get_redeem_multi_amount(
+			pool_id: StableAssetPoolId,
+			amounts: Vec<Balance>,
+		) -> Option<RedeemMultiResult<Balance>>;
+
+		/// Get the A value of the pool at the current block.
+		fn get_a(pool_id: StableAssetPoolId) -> Option<AtLeast64BitUnsigned>;
+
+		/// Get the route of swaps, mints and single asset redeems across pools giving the largest
+		/// output amount, along with that amount. Available since version 2.
+		#[api_version(2)]
+		fn get_best_multi_hop_route(
+			input_asset: AssetId,
+			output_asset: AssetId,
+			input_amount: Balance,
+		) -> Option<(Vec<SwapRouteStep>, Balance)>;
+
+		/// Get the value of one pool token in D, in the pool precision units. Available since version 3.
+		#[api_version(3)]
+		fn get_virtual_price(pool_id: StableAssetPoolId) -> Option<AtLeast64BitUnsigned>;
+
+		/// Get the time-weighted average marginal prices of the pool assets in its first asset and the
+		/// virtual price over the last `window` blocks. Available since version 4.
+		#[api_version(4)]
+		fn get_twap(
+			pool_id: StableAssetPoolId,
+			window: u32,
+		) -> Option<(Vec<AtLeast64BitUnsigned>, AtLeast64BitUnsigned)>;
+
+		/// Get the exponential moving average of the marginal prices of the pool assets in its first
+		/// asset. Available since version 5.
+		#[api_version(5)]
+		fn get_ema_prices(pool_id: StableAssetPoolId) -> Option<Vec<AtLeast64BitUnsigned>>;
+	}
+}
@@ -0,0 +1,517 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the stable asset pallet.
+
+use super::*;
+use crate::traits::StableAsset as StableAssetT;
+use crate::BenchmarkHelper;
+use frame_benchmarking::v2::*;
+use frame_support::traits::EnsureOrigin;
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+// 10**18 / 10**8, the underlying assets are benchmarked with 8 decimals
+const ASSET_PRECISION: u128 = 10_000_000_000;
+const POOL_PRECISION: u128 = 1_000_000_000_000_000_000;
+const INITIAL_A: u128 = 10_000;
+const MINT_FEE: u128 = 10_000_000;
+const SWAP_FEE: u128 = 20_000_000;
+const REDEEM_FEE: u128 = 50_000_000;
+const INITIAL_LIQUIDITY: u128 = 10_000_000_000;
+const USER_AMOUNT: u128 = 100_000_000;
+
+fn number<T: Config>(x: u128) -> T::AtLeast64BitUnsigned {
+	x.into()
+}
+
+fn balance<T: Config>(x: u128) -> T::Balance {
+	number::<T>(x).into()
+}
+
+/// Return the pool asset and `u` underlying assets for a pool.
+fn pool_assets<T: Config>(u: u32) -> (T::AssetId, Vec<T::AssetId>) {
+	let pool_asset = T::BenchmarkHelper::asset_id(0);
+	let assets = (1..=u).map(T::BenchmarkHelper::asset_id).collect();
+	(pool_asset, assets)
+}
+
+/// Create a pool with `u` underlying assets and return its id together with the assets.
+fn create_test_pool<T: Config>(u: u32) -> Result<(StableAssetPoolId, Vec<T::AssetId>), BenchmarkError> {
+	let (pool_asset, assets) = pool_assets::<T>(u);
+	let pool_id = PoolCount::<T>::get();
+	<Pallet<T> as StableAssetT>::create_pool(
+		pool_asset,
+		assets.clone(),
+		vec![number::<T>(ASSET_PRECISION); u as usize],
+		number::<T>(MINT_FEE),
+		number::<T>(SWAP_FEE),
+		number::<T>(REDEEM_FEE),
+		number::<T>(INITIAL_A),
+		account("fee_recipient", 0, SEED),
+		account("yield_recipient", 0, SEED),
+		number::<T>(POOL_PRECISION),
+	)?;
+	Ok((pool_id, assets))
+}
+
+/// Fund `who` with every asset of the pool and mint `amount` of each into the pool.
+fn add_liquidity<T: Config>(
+	who: &T::AccountId,
+	pool_id: StableAssetPoolId,
+	assets: &[T::AssetId],
+	amount: u128,
+) -> Result<(), BenchmarkError> {
+	for asset in assets.iter() {
+		T::Assets::mint_into(*asset, who, balance::<T>(amount.saturating_mul(2)))?;
+	}
+	<Pallet<T> as StableAssetT>::mint(who, pool_id, vec![balance::<T>(amount); assets.len()], Zero::zero())?;
+	Ok(())
+}
+
+/// Create a pool with `u` underlying assets, seeded with liquidity from a separate provider.
+///
+/// The pool is balanced, so each `get_d` and `get_y` of the operations benchmarked on it converges in the
+/// fewest Newton iterations. Their weights are the base the iterations actually taken are charged on top of.
+fn setup_pool<T: Config>(u: u32) -> Result<(StableAssetPoolId, Vec<T::AssetId>), BenchmarkError> {
+	let (pool_id, assets) = create_test_pool::<T>(u)?;
+	let provider: T::AccountId = account("provider", 0, SEED);
+	add_liquidity::<T>(&provider, pool_id, &assets, INITIAL_LIQUIDITY)?;
+	Ok((pool_id, assets))
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn create_pool() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let u = T::PoolAssetLimit::get();
+		let (pool_asset, assets) = pool_assets::<T>(u);
+		let fee_recipient: T::AccountId = account("fee_recipient", 0, SEED);
+		let yield_recipient: T::AccountId = account("yield_recipient", 0, SEED);
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			pool_asset,
+			assets,
+			vec![number::<T>(ASSET_PRECISION); u as usize],
+			number::<T>(MINT_FEE),
+			number::<T>(SWAP_FEE),
+			number::<T>(REDEEM_FEE),
+			number::<T>(INITIAL_A),
+			fee_recipient,
+			yield_recipient,
+			number::<T>(POOL_PRECISION),
+		);
+
+		assert_eq!(PoolCount::<T>::get(), 1);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn mint(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		for asset in assets.iter() {
+			T::Assets::mint_into(*asset, &caller, balance::<T>(USER_AMOUNT))?;
+		}
+		let old_total_supply = Pools::<T>::get(pool_id).ok_or("pool not found")?.total_supply;
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			pool_id,
+			vec![balance::<T>(USER_AMOUNT); u as usize],
+			Zero::zero(),
+		);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert!(pool_info.total_supply > old_total_supply);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn swap(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		T::Assets::mint_into(assets[0], &caller, balance::<T>(USER_AMOUNT))?;
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			pool_id,
+			0,
+			1,
+			balance::<T>(USER_AMOUNT / 2),
+			Zero::zero(),
+			u,
+		);
+
+		assert!(T::Assets::balance(assets[1], &caller) > Zero::zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn swap_exact_output(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		T::Assets::mint_into(assets[0], &caller, balance::<T>(USER_AMOUNT))?;
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			pool_id,
+			0,
+			1,
+			balance::<T>(USER_AMOUNT / 4),
+			balance::<T>(USER_AMOUNT),
+			u,
+		);
+
+		assert!(T::Assets::balance(assets[1], &caller) > Zero::zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn redeem_proportion(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		add_liquidity::<T>(&caller, pool_id, &assets, USER_AMOUNT)?;
+		let pool_asset = Pools::<T>::get(pool_id).ok_or("pool not found")?.pool_asset;
+		let amount = balance::<T>(USER_AMOUNT.saturating_mul(ASSET_PRECISION));
+		let old_pool_balance = T::Assets::balance(pool_asset, &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			pool_id,
+			amount,
+			vec![Zero::zero(); u as usize],
+		);
+
+		assert!(T::Assets::balance(pool_asset, &caller) < old_pool_balance);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn redeem_single(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		add_liquidity::<T>(&caller, pool_id, &assets, USER_AMOUNT)?;
+		let pool_asset = Pools::<T>::get(pool_id).ok_or("pool not found")?.pool_asset;
+		let amount = balance::<T>(USER_AMOUNT.saturating_mul(ASSET_PRECISION));
+		let old_pool_balance = T::Assets::balance(pool_asset, &caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), pool_id, amount, 0, Zero::zero(), u);
+
+		assert!(T::Assets::balance(pool_asset, &caller) < old_pool_balance);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn redeem_multi(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let (pool_id, assets) = setup_pool::<T>(u)?;
+		let caller: T::AccountId = whitelisted_caller();
+		add_liquidity::<T>(&caller, pool_id, &assets, USER_AMOUNT)?;
+		let pool_asset = Pools::<T>::get(pool_id).ok_or("pool not found")?.pool_asset;
+		let max_redeem_amount = T::Assets::balance(pool_asset, &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			pool_id,
+			vec![balance::<T>(USER_AMOUNT / 2); u as usize],
+			max_redeem_amount,
+		);
+
+		assert!(T::Assets::balance(pool_asset, &caller) < max_redeem_amount);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn newton_iteration(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		// The invariant of balanced assets converges in a single iteration.
+		let balances = vec![number::<T>(INITIAL_LIQUIDITY * ASSET_PRECISION); u as usize];
+		let a = number::<T>(INITIAL_A);
+		let (d, iterations);
+
+		#[block]
+		{
+			(d, iterations) = Pallet::<T>::with_newton_iterations(|| Pallet::<T>::get_d(&balances, a));
+		}
+
+		assert!(d.is_some());
+		assert_eq!(iterations, 1);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_a() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(T::PoolAssetLimit::get())?;
+		let future_a_block = frame_system::Pallet::<T>::block_number() + T::MinRampBlocks::get() + 1u32.into();
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			pool_id,
+			number::<T>(INITIAL_A * 2),
+			future_a_block,
+		);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.future_a, number::<T>(INITIAL_A * 2));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn stop_ramp_a() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(T::PoolAssetLimit::get())?;
+		let future_a_block = frame_system::Pallet::<T>::block_number() + T::MinRampBlocks::get() + 1u32.into();
+		<Pallet<T> as StableAssetT>::modify_a(pool_id, number::<T>(INITIAL_A * 2), future_a_block)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.future_a, pool_info.a);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_fees() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			pool_id,
+			Some(number::<T>(MINT_FEE * 2)),
+			Some(number::<T>(SWAP_FEE * 2)),
+			Some(number::<T>(REDEEM_FEE * 2)),
+		);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.swap_fee, number::<T>(SWAP_FEE * 2));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_offpeg_fee_multiplier() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+		let offpeg_fee_multiplier = T::FeePrecision::get()
+			.checked_mul(&number::<T>(2))
+			.ok_or(BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, offpeg_fee_multiplier);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.offpeg_fee_multiplier, offpeg_fee_multiplier);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_admin_fee() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(T::PoolAssetLimit::get())?;
+		let admin_fee = T::FeePrecision::get()
+			.checked_div(&number::<T>(2))
+			.ok_or(BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, admin_fee);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.admin_fee, admin_fee);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_ema_half_life() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+		let half_life: BlockNumberFor<T> = 100u32.into();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, half_life);
+
+		assert_eq!(EmaHalfLives::<T>::get(pool_id), Some(half_life));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn add_asset(u: Linear<2, { T::PoolAssetLimit::get() - 1 }>) -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(u)?;
+		let asset = T::BenchmarkHelper::asset_id(u + 1);
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		T::Assets::mint_into(asset, &pool_info.account_id, balance::<T>(INITIAL_LIQUIDITY))?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, asset, number::<T>(ASSET_PRECISION));
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.assets.len(), (u + 1) as usize);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_asset(u: Linear<3, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(u)?;
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		Restrictions::<T>::insert(pool_id, PoolRestrictions::default().with_input_disabled(u - 1));
+		// the asset is redeemed down to dust, a 10**-5 share of its liquidity
+		let provider: T::AccountId = account("provider", 0, SEED);
+		let mut amounts = vec![Zero::zero(); u as usize];
+		amounts[(u - 1) as usize] = balance::<T>(INITIAL_LIQUIDITY - 100_000);
+		let max_redeem_amount = T::Assets::balance(pool_info.pool_asset, &provider);
+		<Pallet<T> as StableAssetT>::redeem_multi(&provider, pool_id, amounts, max_redeem_amount)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, u - 1);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.assets.len(), (u - 1) as usize);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn start_destroy_pool(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = setup_pool::<T>(u)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id);
+
+		assert_eq!(PoolStatuses::<T>::get(pool_id), PoolStatus::Destroying);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn destroy_pool(u: Linear<2, { T::PoolAssetLimit::get() }>) -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, assets) = create_test_pool::<T>(u)?;
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		// dust of every asset is left to sweep
+		for asset in assets.iter() {
+			T::Assets::mint_into(*asset, &pool_info.account_id, balance::<T>(USER_AMOUNT))?;
+		}
+		<Pallet<T> as StableAssetT>::start_destroy_pool(pool_id)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id);
+
+		assert!(Pools::<T>::get(pool_id).is_none());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_base_pool() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let u = T::PoolAssetLimit::get();
+		let (base_pool_id, assets) = create_test_pool::<T>(u)?;
+		let base_pool_asset = Pools::<T>::get(base_pool_id).ok_or("pool not found")?.pool_asset;
+		let pool_id = PoolCount::<T>::get();
+		<Pallet<T> as StableAssetT>::create_pool(
+			T::BenchmarkHelper::asset_id(u + 1),
+			vec![assets[0], base_pool_asset],
+			vec![number::<T>(ASSET_PRECISION), number::<T>(1)],
+			number::<T>(MINT_FEE),
+			number::<T>(SWAP_FEE),
+			number::<T>(REDEEM_FEE),
+			number::<T>(INITIAL_A),
+			account("fee_recipient", 0, SEED),
+			account("yield_recipient", 0, SEED),
+			number::<T>(POOL_PRECISION),
+		)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, base_pool_id);
+
+		assert_eq!(BasePools::<T>::get(pool_id), Some(base_pool_id));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn modify_recipients() -> Result<(), BenchmarkError> {
+		let origin = T::ListingOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+		let fee_recipient: T::AccountId = account("fee_recipient", 1, SEED);
+		let yield_recipient: T::AccountId = account("yield_recipient", 1, SEED);
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			pool_id,
+			Some(fee_recipient.clone()),
+			Some(yield_recipient),
+		);
+
+		let pool_info = Pools::<T>::get(pool_id).ok_or("pool not found")?;
+		assert_eq!(pool_info.fee_recipient, fee_recipient);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_pool_status() -> Result<(), BenchmarkError> {
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, PoolStatus::Paused);
+
+		assert_eq!(PoolStatuses::<T>::get(pool_id), PoolStatus::Paused);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_paused() -> Result<(), BenchmarkError> {
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, true);
+
+		assert!(Paused::<T>::get());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_restrictions() -> Result<(), BenchmarkError> {
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let (pool_id, _) = create_test_pool::<T>(T::PoolAssetLimit::get())?;
+		let restrictions = PoolRestrictions::default()
+			.with_operation_disabled(PoolOperation::Mint)
+			.with_output_disabled(0);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, pool_id, restrictions);
+
+		assert_eq!(Restrictions::<T>::get(pool_id), restrictions);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
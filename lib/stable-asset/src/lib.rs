@@ -29,65 +29,307 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod migrations;
 pub mod weights;
 
-use crate::traits::StableAsset;
+use crate::traits::{RateProvider, StableAsset};
 use frame_support::{
-	dispatch::DispatchResult,
+	dispatch::{DispatchResult, DispatchResultWithPostInfo},
+	dispatch_context::{run_in_context, with_context},
 	ensure,
+	storage::with_storage_layer,
 	traits::{
 		fungibles::{Inspect, Mutate},
 		tokens::{Fortitude, Precision, Preservation},
 		Get,
 	},
 	weights::Weight,
+	BoundedVec,
 };
 use frame_system::pallet_prelude::*;
-use parity_scale_codec::{Decode, Encode};
+use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
 use sp_core::U512;
 use sp_runtime::{
 	traits::{AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero},
 	DispatchError, SaturatedConversion,
 };
-use sp_std::prelude::*;
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
 
 pub type PoolTokenIndex = u32;
 
 pub type StableAssetPoolId = u32;
 
 const NUMBER_OF_ITERATIONS_TO_CONVERGE: i32 = 255; // the number of iterations to sum d and y
+const MAX_EXACT_OUTPUT_ADJUSTMENTS: u32 = 4; // the most times an exact output quote is raised to cover the output
+const RATE_CHANGE_SOLVES: u32 = 1; // the D of the recorded balances of a pool at changed rates
+const QUOTE_SOLVES: u32 = 2; // the D of the balances of a pool with its yield, and the solve of the operation
+
+// the most get_d and get_y calls of a mint, swap or redeem: collecting the yield, quoting the operation and
+// collecting the fees, for the D of the balances with them
+const MAX_SOLVES_PER_OPERATION: u32 = RATE_CHANGE_SOLVES + 1 + QUOTE_SOLVES + 1;
+// the most get_d and get_y calls of a route step, quoting a mint before the operation collects the yield
+const MAX_SOLVES_PER_ROUTE_STEP: u32 = MAX_SOLVES_PER_OPERATION + RATE_CHANGE_SOLVES + QUOTE_SOLVES;
+// the most get_d and get_y calls of an exact output swap: its quote solves for y twice, then each adjustment
+// and the swap itself quote a swap
+const MAX_SOLVES_PER_EXACT_OUTPUT: u32 =
+	MAX_SOLVES_PER_OPERATION + 1 + (MAX_EXACT_OUTPUT_ADJUSTMENTS + 1) * QUOTE_SOLVES;
+
+const EMA_PRECISION: u128 = 1_000_000_000_000_000_000; // the precision of EMA weights
+const LN_2: u128 = 693_147_180_559_945_309; // ln 2 in EMA_PRECISION units
+
+/// The calls of `get_d` and `get_y` made in the current dispatch and the Newton iterations they took,
+/// kept in its dispatch context.
+#[derive(Clone, Copy, Default)]
+struct NewtonIterations {
+	solves: u32,
+	iterations: u32,
+}
 
-#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
-pub struct StableAssetPoolInfo<AssetId, AtLeast64BitUnsigned, Balance, AccountId, BlockNumber> {
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(PoolAssetLimit))]
+pub struct StableAssetPoolInfo<AssetId, AtLeast64BitUnsigned, Balance, AccountId, BlockNumber, PoolAssetLimit: Get<u32>>
+{
 	pub pool_asset: AssetId,
-	pub assets: Vec<AssetId>,
-	pub precisions: Vec<AtLeast64BitUnsigned>,
+	pub assets: BoundedVec<AssetId, PoolAssetLimit>,
+	pub precisions: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
 	pub mint_fee: AtLeast64BitUnsigned,
 	pub swap_fee: AtLeast64BitUnsigned,
 	pub redeem_fee: AtLeast64BitUnsigned,
+	/// Scales the swap fee up to `swap_fee * offpeg_fee_multiplier / FeePrecision` as the swap moves the pool
+	/// away from balance. At or below `FeePrecision` the swap fee is flat.
+	pub offpeg_fee_multiplier: AtLeast64BitUnsigned,
+	/// The share of every fee sent to `fee_recipient`, in `FeePrecision` units. The rest stays in the pool
+	/// for the liquidity providers.
+	pub admin_fee: AtLeast64BitUnsigned,
+	/// The D of the pool.
 	pub total_supply: Balance,
+	/// The amount of pool token minted by the pool. It grows slower than `total_supply` as the fees kept
+	/// for the liquidity providers raise the value of each pool token.
+	pub pool_token_supply: Balance,
 	pub a: AtLeast64BitUnsigned,
 	pub a_block: BlockNumber,
 	pub future_a: AtLeast64BitUnsigned,
 	pub future_a_block: BlockNumber,
-	pub balances: Vec<Balance>,
+	pub balances: BoundedVec<Balance, PoolAssetLimit>,
 	pub fee_recipient: AccountId,
 	pub account_id: AccountId,
 	pub yield_recipient: AccountId,
 	pub precision: AtLeast64BitUnsigned,
 }
 
+/// A pool created at genesis, see `GenesisConfig`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GenesisPool<AssetId, AtLeast64BitUnsigned, Balance, AccountId> {
+	pub pool_asset: AssetId,
+	pub assets: Vec<AssetId>,
+	pub precisions: Vec<AtLeast64BitUnsigned>,
+	pub mint_fee: AtLeast64BitUnsigned,
+	pub swap_fee: AtLeast64BitUnsigned,
+	pub redeem_fee: AtLeast64BitUnsigned,
+	pub initial_a: AtLeast64BitUnsigned,
+	pub fee_recipient: AccountId,
+	pub yield_recipient: AccountId,
+	pub precision: AtLeast64BitUnsigned,
+	/// The account minting the first pool tokens and the amount of each asset it deposits. The account must
+	/// hold the assets by the time this pallet's genesis is built.
+	pub initial_liquidity: Option<(AccountId, Vec<Balance>)>,
+}
+
+/// The status of a pool.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+pub enum PoolStatus {
+	/// All operations are allowed.
+	#[default]
+	Active,
+	/// Only `redeem_proportion` is allowed.
+	Paused,
+	/// Only redeems are allowed, until `destroy_pool` removes the pool. Set by `start_destroy_pool`.
+	Destroying,
+}
+
+/// An operation of a pool which can be disabled with `PoolRestrictions`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+pub enum PoolOperation {
+	Mint,
+	Swap,
+	RedeemProportion,
+	RedeemSingle,
+	RedeemMulti,
+}
+
+impl PoolOperation {
+	/// The bit of the operation in `PoolRestrictions::disabled_operations`.
+	pub fn flag(self) -> u8 {
+		1 << (self as u8)
+	}
+}
+
+/// The operations and asset indexes disabled on a pool, as bit sets.
+///
+/// Asset index `i` is disabled by setting bit `1 << i` of `disabled_inputs` or `disabled_outputs`. Inputs are
+/// the assets put into the pool by mint and swap, outputs are the assets taken out by swap, redeem_single and
+/// redeem_multi. redeem_proportion is only subject to its operation flag.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+pub struct PoolRestrictions {
+	pub disabled_operations: u8,
+	pub disabled_inputs: u32,
+	pub disabled_outputs: u32,
+}
+
+impl PoolRestrictions {
+	pub fn with_operation_disabled(mut self, operation: PoolOperation) -> Self {
+		self.disabled_operations |= operation.flag();
+		self
+	}
+
+	pub fn with_input_disabled(mut self, index: PoolTokenIndex) -> Self {
+		self.disabled_inputs |= 1u32.checked_shl(index).unwrap_or_default();
+		self
+	}
+
+	pub fn with_output_disabled(mut self, index: PoolTokenIndex) -> Self {
+		self.disabled_outputs |= 1u32.checked_shl(index).unwrap_or_default();
+		self
+	}
+
+	pub fn is_operation_disabled(&self, operation: PoolOperation) -> bool {
+		self.disabled_operations & operation.flag() != 0
+	}
+
+	pub fn is_input_disabled(&self, index: PoolTokenIndex) -> bool {
+		self.disabled_inputs & 1u32.checked_shl(index).unwrap_or_default() != 0
+	}
+
+	pub fn is_output_disabled(&self, index: PoolTokenIndex) -> bool {
+		self.disabled_outputs & 1u32.checked_shl(index).unwrap_or_default() != 0
+	}
+
+	/// The restrictions left once the asset at `index` is removed from the pool, moving the asset indexes
+	/// after it down by one.
+	pub fn without_index(mut self, index: PoolTokenIndex) -> Self {
+		let remove_bit = |bits: u32| -> u32 {
+			let lower = 1u32.checked_shl(index).unwrap_or_default().wrapping_sub(1);
+			let upper = bits
+				.checked_shr(index.saturating_add(1))
+				.unwrap_or_default()
+				.checked_shl(index)
+				.unwrap_or_default();
+			(bits & lower) | upper
+		};
+		self.disabled_inputs = remove_bit(self.disabled_inputs);
+		self.disabled_outputs = remove_bit(self.disabled_outputs);
+		self
+	}
+}
+
+/// A single step of a swap route, see `StableAsset::swap_route`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+pub enum SwapRouteStep {
+	/// Swap the asset at `input_index` into the asset at `output_index` of the pool.
+	Swap {
+		pool_id: StableAssetPoolId,
+		input_index: PoolTokenIndex,
+		output_index: PoolTokenIndex,
+	},
+	/// Mint the pool token with the asset at `input_index` of the pool only.
+	Mint {
+		pool_id: StableAssetPoolId,
+		input_index: PoolTokenIndex,
+	},
+	/// Redeem the pool token into the asset at `output_index` of the pool only.
+	RedeemSingle {
+		pool_id: StableAssetPoolId,
+		output_index: PoolTokenIndex,
+	},
+}
+
+impl SwapRouteStep {
+	pub fn pool_id(&self) -> StableAssetPoolId {
+		match self {
+			SwapRouteStep::Swap { pool_id, .. }
+			| SwapRouteStep::Mint { pool_id, .. }
+			| SwapRouteStep::RedeemSingle { pool_id, .. } => *pool_id,
+		}
+	}
+}
+
+/// The price oracle of a pool, updated by every operation collecting the fees of the pool.
+///
+/// Every block is priced at the prices left by the last operation before it, and the cumulative prices
+/// sum the prices of every block since the oracle started. The difference of two cumulative prices over
+/// the blocks between them is a time-weighted average price, which cannot be moved within a single block.
+/// Prices are in the pool `precision` units.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(PoolAssetLimit))]
+pub struct PoolOracle<AtLeast64BitUnsigned, BlockNumber, PoolAssetLimit: Get<u32>> {
+	/// The block of the last update.
+	pub block: BlockNumber,
+	/// The marginal price of each asset in the first asset of the pool at the last update.
+	pub prices: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
+	/// The virtual price of the pool at the last update.
+	pub virtual_price: AtLeast64BitUnsigned,
+	/// The sum of the marginal prices of every block up to `block`.
+	pub price_cumulatives: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
+	/// The sum of the virtual prices of every block up to `block`.
+	pub virtual_price_cumulative: AtLeast64BitUnsigned,
+	/// The index in `OracleObservations` of the observation at `block`.
+	pub observation_index: u32,
+}
+
+/// The cumulative prices of a pool oracle at a block, see `PoolOracle`.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(PoolAssetLimit))]
+pub struct OracleObservation<AtLeast64BitUnsigned, BlockNumber, PoolAssetLimit: Get<u32>> {
+	pub block: BlockNumber,
+	pub price_cumulatives: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
+	pub virtual_price_cumulative: AtLeast64BitUnsigned,
+}
+
 pub trait WeightInfo {
 	fn create_pool() -> Weight;
 	fn modify_a() -> Weight;
+	fn stop_ramp_a() -> Weight;
 	fn modify_fees() -> Weight;
 	fn modify_recipients() -> Weight;
+	fn modify_offpeg_fee_multiplier() -> Weight;
+	fn modify_admin_fee() -> Weight;
+	fn modify_ema_half_life() -> Weight;
+	fn add_asset(u: u32) -> Weight;
+	fn remove_asset(u: u32) -> Weight;
+	fn start_destroy_pool(u: u32) -> Weight;
+	fn destroy_pool(u: u32) -> Weight;
+	fn set_base_pool() -> Weight;
+	fn set_pool_status() -> Weight;
+	fn set_paused() -> Weight;
+	fn set_restrictions() -> Weight;
 	fn mint(u: u32) -> Weight;
 	fn swap(u: u32) -> Weight;
+	fn swap_exact_output(u: u32) -> Weight;
 	fn redeem_proportion(u: u32) -> Weight;
 	fn redeem_single(u: u32) -> Weight;
 	fn redeem_multi(u: u32) -> Weight;
+	fn newton_iteration(u: u32) -> Weight;
+}
+
+/// Helper to create the assets used by the benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+	/// Return an asset ID that can be minted into by `Config::Assets`. Distinct `id`s must map to
+	/// distinct assets, and the asset for each `id` must be accepted as a pool asset.
+	fn asset_id(id: u32) -> AssetId;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AssetId: From<u32>> BenchmarkHelper<AssetId> for () {
+	fn asset_id(id: u32) -> AssetId {
+		id.into()
+	}
 }
 
 pub mod traits {
@@ -97,12 +339,26 @@ pub mod traits {
 		fn validate(a: AssetId) -> bool;
 	}
 
+	/// The redemption rates of yield-bearing pool assets, such as liquid staking tokens.
+	pub trait RateProvider<AssetId, AtLeast64BitUnsigned> {
+		/// The value of one unit of `asset`, in `RatePrecision` units. `None` for assets without a rate,
+		/// which are valued at one.
+		fn get_rate(asset: AssetId) -> Option<AtLeast64BitUnsigned>;
+	}
+
+	impl<AssetId, AtLeast64BitUnsigned> RateProvider<AssetId, AtLeast64BitUnsigned> for () {
+		fn get_rate(_asset: AssetId) -> Option<AtLeast64BitUnsigned> {
+			None
+		}
+	}
+
 	pub trait StableAsset {
 		type AssetId;
 		type AtLeast64BitUnsigned;
 		type Balance;
 		type AccountId;
 		type BlockNumber;
+		type PoolAssetLimit: Get<u32>;
 
 		fn pool_count() -> StableAssetPoolId;
 
@@ -115,6 +371,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		>;
 
@@ -148,6 +405,16 @@ pub mod traits {
 			asset_length: u32,
 		) -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError>;
 
+		fn swap_exact_output(
+			who: &Self::AccountId,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dy: Self::Balance,
+			max_dx: Self::Balance,
+			asset_length: u32,
+		) -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError>;
+
 		fn redeem_proportion(
 			who: &Self::AccountId,
 			pool_id: StableAssetPoolId,
@@ -171,6 +438,22 @@ pub mod traits {
 			max_redeem_amount: Self::Balance,
 		) -> DispatchResult;
 
+		fn swap_route(
+			who: &Self::AccountId,
+			route: Vec<SwapRouteStep>,
+			input_amount: Self::Balance,
+			min_output_amount: Self::Balance,
+		) -> sp_std::result::Result<Self::Balance, DispatchError>;
+
+		fn swap_underlying(
+			who: &Self::AccountId,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dx: Self::Balance,
+			min_dy: Self::Balance,
+		) -> sp_std::result::Result<Self::Balance, DispatchError>;
+
 		fn collect_fee(
 			pool_id: StableAssetPoolId,
 			pool_info: &mut StableAssetPoolInfo<
@@ -179,6 +462,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		) -> DispatchResult;
 
@@ -190,6 +474,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		) -> DispatchResult;
 
@@ -201,6 +486,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		) -> DispatchResult;
 
@@ -210,6 +496,22 @@ pub mod traits {
 			future_a_block: Self::BlockNumber,
 		) -> DispatchResult;
 
+		fn stop_ramp_a(pool_id: StableAssetPoolId) -> DispatchResult;
+
+		fn add_asset(
+			pool_id: StableAssetPoolId,
+			asset: Self::AssetId,
+			precision: Self::AtLeast64BitUnsigned,
+		) -> DispatchResult;
+
+		fn remove_asset(pool_id: StableAssetPoolId, index: PoolTokenIndex) -> DispatchResult;
+
+		fn start_destroy_pool(pool_id: StableAssetPoolId) -> DispatchResult;
+
+		fn destroy_pool(pool_id: StableAssetPoolId) -> DispatchResult;
+
+		fn set_base_pool(pool_id: StableAssetPoolId, base_pool_id: StableAssetPoolId) -> DispatchResult;
+
 		fn get_collect_yield_amount(
 			pool_info: &StableAssetPoolInfo<
 				Self::AssetId,
@@ -217,6 +519,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		) -> Option<
 			StableAssetPoolInfo<
@@ -225,6 +528,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		>;
 
@@ -235,6 +539,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		) -> Option<
 			StableAssetPoolInfo<
@@ -243,6 +548,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 		>;
 
@@ -253,6 +559,7 @@ pub mod traits {
 				Self::Balance,
 				Self::AccountId,
 				Self::BlockNumber,
+				Self::PoolAssetLimit,
 			>,
 			amount_bal: Self::Balance,
 		) -> Option<RedeemProportionResult<Self::Balance>>;
@@ -268,6 +575,18 @@ pub mod traits {
 			input_amount: Self::Balance,
 		) -> Option<(StableAssetPoolId, PoolTokenIndex, PoolTokenIndex, Self::Balance)>;
 
+		/// Get the best swap route of at most `MaxRouteLength` steps across all pools, including
+		/// routes through pool tokens. Each pool is used at most once in a route.
+		///  params:
+		/// - input_asset: the input asset.
+		/// - output_asset: the output asset.
+		/// - input_amount: the input amount of input asset.
+		fn get_best_multi_hop_route(
+			input_asset: Self::AssetId,
+			output_asset: Self::AssetId,
+			input_amount: Self::Balance,
+		) -> Option<(Vec<SwapRouteStep>, Self::Balance)>;
+
 		/// Get the swap result at exact input amount.
 		///  params:
 		/// - pool_id: the pool id.
@@ -293,20 +612,91 @@ pub mod traits {
 			output_index: PoolTokenIndex,
 			dy_bal: Self::Balance,
 		) -> Option<SwapResult<Self::Balance>>;
+
+		/// Get the mint result at exact input amounts.
+		///  params:
+		/// - pool_id: the pool id.
+		/// - amounts: the input amounts of underlying assets.
+		fn get_mint_output_amount(
+			pool_id: StableAssetPoolId,
+			amounts: Vec<Self::Balance>,
+		) -> Option<MintResult<Self::Balance>>;
+
+		/// Get the proportional redeem result at exact pool token amount.
+		///  params:
+		/// - pool_id: the pool id.
+		/// - amount: the pool token amount to be redeemed.
+		fn get_redeem_proportion_output_amount(
+			pool_id: StableAssetPoolId,
+			amount: Self::Balance,
+		) -> Option<RedeemProportionResult<Self::Balance>>;
+
+		/// Get the single asset redeem result at exact pool token amount.
+		///  params:
+		/// - pool_id: the pool id.
+		/// - amount: the pool token amount to be redeemed.
+		/// - output_index: the asset index of output asset.
+		fn get_redeem_single_output_amount(
+			pool_id: StableAssetPoolId,
+			amount: Self::Balance,
+			output_index: PoolTokenIndex,
+		) -> Option<RedeemSingleResult<Self::Balance>>;
+
+		/// Get the multi asset redeem result at exact output amounts.
+		///  params:
+		/// - pool_id: the pool id.
+		/// - amounts: the output amounts of underlying assets.
+		fn get_redeem_multi_input_amount(
+			pool_id: StableAssetPoolId,
+			amounts: Vec<Self::Balance>,
+		) -> Option<RedeemMultiResult<Self::Balance>>;
+
+		/// Get the A value of the pool at the current block.
+		///  params:
+		/// - pool_id: the pool id.
+		fn get_current_a(pool_id: StableAssetPoolId) -> Option<Self::AtLeast64BitUnsigned>;
+
+		/// Get the value of one pool token of the pool in D, in the pool `precision` units.
+		///  params:
+		/// - pool_id: the pool id.
+		fn get_virtual_price(pool_id: StableAssetPoolId) -> Option<Self::AtLeast64BitUnsigned>;
+
+		/// Get the time-weighted average prices of the pool over the last `window` blocks: the marginal
+		/// price of each asset in the first asset of the pool and the virtual price, in the pool `precision`
+		/// units. `None` if the oracle of the pool does not reach back that far.
+		///  params:
+		/// - pool_id: the pool id.
+		/// - window: the number of blocks to average over.
+		fn get_twap(
+			pool_id: StableAssetPoolId,
+			window: Self::BlockNumber,
+		) -> Option<(Vec<Self::AtLeast64BitUnsigned>, Self::AtLeast64BitUnsigned)>;
+
+		/// Get the exponential moving average of the marginal price of each asset of the pool in its first
+		/// asset, in the pool `precision` units. `None` if the pool has no EMA half-life.
+		///  params:
+		/// - pool_id: the pool id.
+		fn get_ema_prices(pool_id: StableAssetPoolId) -> Option<Vec<Self::AtLeast64BitUnsigned>>;
 	}
 }
 
+// the `TryState` impl generated from the hooks, at module level, maps the try_state error to log it
+#[cfg_attr(feature = "try-runtime", allow(clippy::manual_inspect))]
 #[frame_support::pallet]
 pub mod pallet {
-	use super::{PoolTokenIndex, StableAssetPoolId, StableAssetPoolInfo};
-	use crate::traits::{StableAsset, ValidateAssetId};
+	use super::{
+		GenesisPool, OracleObservation, PoolOracle, PoolRestrictions, PoolStatus, PoolTokenIndex, StableAssetPoolId,
+		StableAssetPoolInfo, SwapRouteStep, MAX_SOLVES_PER_EXACT_OUTPUT, MAX_SOLVES_PER_OPERATION,
+		MAX_SOLVES_PER_ROUTE_STEP,
+	};
+	use crate::traits::{RateProvider, StableAsset, ValidateAssetId};
 	use crate::WeightInfo;
 	use frame_support::traits::tokens::fungibles;
 	use frame_support::{dispatch::DispatchResult, pallet_prelude::*, traits::EnsureOrigin, PalletId};
 	use frame_system::pallet_prelude::*;
 	use parity_scale_codec::Codec;
 	use sp_runtime::{
-		traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero},
+		traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, MaybeSerializeDeserialize, One, Zero},
 		FixedPointOperand,
 	};
 	use sp_std::prelude::*;
@@ -315,11 +705,20 @@ pub mod pallet {
 	pub trait Config: frame_system::Config {
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-		type AssetId: Parameter + Ord + Copy;
-		type Balance: Parameter + Codec + Copy + Ord + From<Self::AtLeast64BitUnsigned> + Zero;
+		type AssetId: Parameter + Ord + Copy + MaxEncodedLen + MaybeSerializeDeserialize;
+		type Balance: Parameter
+			+ Codec
+			+ Copy
+			+ Ord
+			+ From<Self::AtLeast64BitUnsigned>
+			+ Zero
+			+ MaxEncodedLen
+			+ MaybeSerializeDeserialize;
 		type Assets: fungibles::Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>
 			+ fungibles::Mutate<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>;
 		type AtLeast64BitUnsigned: Parameter
+			+ MaxEncodedLen
+			+ MaybeSerializeDeserialize
 			+ CheckedAdd
 			+ CheckedSub
 			+ CheckedMul
@@ -339,23 +738,85 @@ pub mod pallet {
 		type PalletId: Get<PalletId>;
 		#[pallet::constant]
 		type FeePrecision: Get<Self::AtLeast64BitUnsigned>;
+		/// The maximum mint fee of a pool, in `FeePrecision` units. Must be below `FeePrecision`.
+		#[pallet::constant]
+		type MaxMintFee: Get<Self::AtLeast64BitUnsigned>;
+		/// The maximum swap fee of a pool, in `FeePrecision` units. Must be below `FeePrecision`.
+		#[pallet::constant]
+		type MaxSwapFee: Get<Self::AtLeast64BitUnsigned>;
+		/// The maximum redeem fee of a pool, in `FeePrecision` units. Must be below `FeePrecision`.
+		#[pallet::constant]
+		type MaxRedeemFee: Get<Self::AtLeast64BitUnsigned>;
 		#[pallet::constant]
 		type SwapExactOverAmount: Get<Self::AtLeast64BitUnsigned>;
 		#[pallet::constant]
 		type APrecision: Get<Self::AtLeast64BitUnsigned>;
 		#[pallet::constant]
 		type PoolAssetLimit: Get<u32>;
+		/// The maximum A value of a pool, at creation or ramped to by `modify_a`, in `APrecision` units.
+		#[pallet::constant]
+		type MaxA: Get<Self::AtLeast64BitUnsigned>;
+		/// The minimum number of blocks of an A ramp scheduled by `modify_a`.
+		#[pallet::constant]
+		type MinRampBlocks: Get<BlockNumberFor<Self>>;
+		/// The maximum factor by which a single `modify_a` may raise or lower A.
+		#[pallet::constant]
+		type MaxAChangeFactor: Get<Self::AtLeast64BitUnsigned>;
+		/// The maximum number of steps in a swap route.
+		#[pallet::constant]
+		type MaxRouteLength: Get<u32>;
+		/// The number of observations kept by the price oracle of each pool, one per block with a pool
+		/// operation. It bounds the window of `StableAsset::get_twap`.
+		#[pallet::constant]
+		type MaxOracleObservations: Get<u32>;
+		/// The pool token supply at or below which `destroy_pool` removes a pool being destroyed.
+		#[pallet::constant]
+		type PoolDustThreshold: Get<Self::Balance>;
+		/// The balance of an asset, normalised to the pool precision, at or below which `remove_asset`
+		/// removes it from a pool.
+		#[pallet::constant]
+		type AssetDustThreshold: Get<Self::Balance>;
+		/// The account receiving the assets left in the account of a destroyed pool or behind a removed
+		/// asset.
+		type DustAccount: Get<Self::AccountId>;
 		type WeightInfo: WeightInfo;
 		type EnsurePoolAssetId: ValidateAssetId<Self::AssetId>;
+		/// The rates multiplying the balances of pool assets in the invariant. Assets without a rate
+		/// are valued at one. As in stableswap-ng, the D gained or lost when a rate changes accrues to the
+		/// liquidity providers, raising or lowering the virtual price. Only the D gained by rebasing
+		/// balances is collected as yield and minted in pool tokens to the yield recipient of the pool.
+		type RateProvider: RateProvider<Self::AssetId, Self::AtLeast64BitUnsigned>;
+		/// The precision of the rates of `RateProvider`, i.e. the rate valuing an asset at one.
+		#[pallet::constant]
+		type RatePrecision: Get<Self::AtLeast64BitUnsigned>;
 
 		/// The origin which may create pool or modify pool.
 		type ListingOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The origin which may pause or unpause pools.
+		type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: crate::BenchmarkHelper<Self::AssetId>;
 	}
 
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
 	#[pallet::pallet]
-	#[pallet::without_storage_info]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
+	/// The `PoolAssetLimit` of the runtime, bounding the vectors of `StableAssetPoolInfo`.
+	#[derive(CloneNoBound, frame_support::DefaultNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound)]
+	pub struct PoolAssetLimitOf<T>(PhantomData<T>);
+
+	impl<T: Config> Get<u32> for PoolAssetLimitOf<T> {
+		fn get() -> u32 {
+			T::PoolAssetLimit::get()
+		}
+	}
+
 	#[pallet::storage]
 	#[pallet::getter(fn pool_count)]
 	pub type PoolCount<T: Config> = StorageValue<_, StableAssetPoolId, ValueQuery>;
@@ -366,9 +827,122 @@ pub mod pallet {
 		_,
 		Blake2_128Concat,
 		StableAssetPoolId,
-		StableAssetPoolInfo<T::AssetId, T::AtLeast64BitUnsigned, T::Balance, T::AccountId, BlockNumberFor<T>>,
+		StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pool_status)]
+	pub type PoolStatuses<T: Config> = StorageMap<_, Blake2_128Concat, StableAssetPoolId, PoolStatus, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pool_restrictions)]
+	pub type Restrictions<T: Config> = StorageMap<_, Blake2_128Concat, StableAssetPoolId, PoolRestrictions, ValueQuery>;
+
+	/// The pool minting each pool asset.
+	#[pallet::storage]
+	#[pallet::getter(fn pool_asset_pool)]
+	pub type PoolAssetPools<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, StableAssetPoolId>;
+
+	/// The base pool of each metapool, whose pool token the metapool values at the virtual price of the
+	/// base pool. See `set_base_pool`.
+	#[pallet::storage]
+	#[pallet::getter(fn base_pool)]
+	pub type BasePools<T: Config> = StorageMap<_, Blake2_128Concat, StableAssetPoolId, StableAssetPoolId>;
+
+	/// The metapools of each base pool.
+	#[pallet::storage]
+	pub type MetaPools<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, StableAssetPoolId, Blake2_128Concat, StableAssetPoolId, ()>;
+
+	/// The price oracle of each pool.
+	#[pallet::storage]
+	#[pallet::getter(fn pool_oracle)]
+	pub type Oracles<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		StableAssetPoolId,
+		PoolOracle<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>,
+	>;
+
+	/// The last `MaxOracleObservations` observations of the price oracle of each pool, a ring buffer
+	/// written at `PoolOracle::observation_index`.
+	#[pallet::storage]
+	pub type OracleObservations<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		StableAssetPoolId,
+		Twox64Concat,
+		u32,
+		OracleObservation<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>,
 	>;
 
+	/// The half-life of the EMA prices of each pool, in blocks. Pools without one keep no EMA.
+	#[pallet::storage]
+	#[pallet::getter(fn ema_half_life)]
+	pub type EmaHalfLives<T: Config> = StorageMap<_, Blake2_128Concat, StableAssetPoolId, BlockNumberFor<T>>;
+
+	/// The EMA prices of each pool as of `PoolOracle::block`, before the operations of that block.
+	#[pallet::storage]
+	pub type EmaPrices<T: Config> =
+		StorageMap<_, Blake2_128Concat, StableAssetPoolId, BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>>>;
+
+	/// The rates the recorded balances of each pool were last normalised at. The D the recorded balances
+	/// gain or lose when the rates change accrues to the liquidity providers, see `Config::RateProvider`.
+	#[pallet::storage]
+	pub type PoolRates<T: Config> =
+		StorageMap<_, Blake2_128Concat, StableAssetPoolId, BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>>>;
+
+	/// Whether all pools are paused.
+	#[pallet::storage]
+	#[pallet::getter(fn paused)]
+	pub type Paused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The pools to create, in pool id order.
+		pub pools: Vec<GenesisPool<T::AssetId, T::AtLeast64BitUnsigned, T::Balance, T::AccountId>>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for pool in self.pools.iter() {
+				let pool_id = PoolCount::<T>::get();
+				assert!(
+					T::EnsurePoolAssetId::validate(pool.pool_asset),
+					"genesis pool {pool_id} has an invalid pool asset"
+				);
+				if let Err(e) = <Pallet<T> as StableAsset>::create_pool(
+					pool.pool_asset,
+					pool.assets.clone(),
+					pool.precisions.clone(),
+					pool.mint_fee,
+					pool.swap_fee,
+					pool.redeem_fee,
+					pool.initial_a,
+					pool.fee_recipient.clone(),
+					pool.yield_recipient.clone(),
+					pool.precision,
+				) {
+					panic!("genesis pool {pool_id} cannot be created: {e:?}");
+				}
+				if let Some((provider, amounts)) = &pool.initial_liquidity {
+					if let Err(e) = <Pallet<T> as StableAsset>::mint(provider, pool_id, amounts.clone(), Zero::zero()) {
+						panic!("initial liquidity of genesis pool {pool_id} cannot be minted: {e:?}");
+					}
+				}
+			}
+		}
+	}
+
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
@@ -387,6 +961,7 @@ pub mod pallet {
 			balances: Vec<T::Balance>,
 			total_supply: T::Balance,
 			fee_amount: T::Balance,
+			lp_fee_amount: T::Balance,
 			output_amount: T::Balance,
 		},
 		TokenSwapped {
@@ -399,6 +974,8 @@ pub mod pallet {
 			min_output_amount: T::Balance,
 			balances: Vec<T::Balance>,
 			total_supply: T::Balance,
+			fee_amount: T::Balance,
+			lp_fee_amount: T::Balance,
 			output_amount: T::Balance,
 		},
 		RedeemedProportion {
@@ -410,6 +987,7 @@ pub mod pallet {
 			balances: Vec<T::Balance>,
 			total_supply: T::Balance,
 			fee_amount: T::Balance,
+			lp_fee_amount: T::Balance,
 			output_amounts: Vec<T::Balance>,
 		},
 		RedeemedSingle {
@@ -422,6 +1000,7 @@ pub mod pallet {
 			balances: Vec<T::Balance>,
 			total_supply: T::Balance,
 			fee_amount: T::Balance,
+			lp_fee_amount: T::Balance,
 			output_amount: T::Balance,
 		},
 		RedeemedMulti {
@@ -433,6 +1012,7 @@ pub mod pallet {
 			balances: Vec<T::Balance>,
 			total_supply: T::Balance,
 			fee_amount: T::Balance,
+			lp_fee_amount: T::Balance,
 			input_amount: T::Balance,
 		},
 		BalanceUpdated {
@@ -447,6 +1027,8 @@ pub mod pallet {
 			new_total_supply: T::Balance,
 			who: T::AccountId,
 			amount: T::Balance,
+			/// The virtual price of the pool after the yield, see `StableAsset::get_virtual_price`.
+			virtual_price: T::AtLeast64BitUnsigned,
 		},
 		FeeCollected {
 			pool_id: StableAssetPoolId,
@@ -457,6 +1039,9 @@ pub mod pallet {
 			new_total_supply: T::Balance,
 			who: T::AccountId,
 			amount: T::Balance,
+			lp_fee_amount: T::Balance,
+			/// The virtual price of the pool after the fee, see `StableAsset::get_virtual_price`.
+			virtual_price: T::AtLeast64BitUnsigned,
 		},
 		AModified {
 			pool_id: StableAssetPoolId,
@@ -474,6 +1059,75 @@ pub mod pallet {
 			fee_recipient: T::AccountId,
 			yield_recipient: T::AccountId,
 		},
+		PoolStatusModified {
+			pool_id: StableAssetPoolId,
+			status: PoolStatus,
+		},
+		PausedModified {
+			paused: bool,
+		},
+		RestrictionsModified {
+			pool_id: StableAssetPoolId,
+			restrictions: PoolRestrictions,
+		},
+		RouteSwapped {
+			swapper: T::AccountId,
+			route: Vec<SwapRouteStep>,
+			input_asset: T::AssetId,
+			output_asset: T::AssetId,
+			input_amount: T::Balance,
+			min_output_amount: T::Balance,
+			output_amount: T::Balance,
+		},
+		RampStopped {
+			pool_id: StableAssetPoolId,
+			a: T::AtLeast64BitUnsigned,
+			time: BlockNumberFor<T>,
+		},
+		OffpegFeeMultiplierModified {
+			pool_id: StableAssetPoolId,
+			offpeg_fee_multiplier: T::AtLeast64BitUnsigned,
+		},
+		AdminFeeModified {
+			pool_id: StableAssetPoolId,
+			admin_fee: T::AtLeast64BitUnsigned,
+		},
+		EmaHalfLifeModified {
+			pool_id: StableAssetPoolId,
+			half_life: BlockNumberFor<T>,
+		},
+		AssetAdded {
+			pool_id: StableAssetPoolId,
+			asset: T::AssetId,
+			precision: T::AtLeast64BitUnsigned,
+			balance: T::Balance,
+		},
+		AssetRemoved {
+			pool_id: StableAssetPoolId,
+			asset: T::AssetId,
+			who: T::AccountId,
+			amount: T::Balance,
+		},
+		PoolDestroying {
+			pool_id: StableAssetPoolId,
+		},
+		PoolDestroyed {
+			pool_id: StableAssetPoolId,
+			who: T::AccountId,
+			amounts: Vec<T::Balance>,
+		},
+		LossShared {
+			pool_id: StableAssetPoolId,
+			a: T::AtLeast64BitUnsigned,
+			old_total_supply: T::Balance,
+			new_total_supply: T::Balance,
+			/// The virtual price of the pool after the loss, see `StableAsset::get_virtual_price`.
+			virtual_price: T::AtLeast64BitUnsigned,
+		},
+		BasePoolSet {
+			pool_id: StableAssetPoolId,
+			base_pool_id: StableAssetPoolId,
+		},
 	}
 
 	#[pallet::error]
@@ -489,61 +1143,127 @@ pub mod pallet {
 		SwapUnderMin,
 		RedeemUnderMin,
 		RedeemOverMax,
+		InvalidRoute,
+		SwapOverMax,
+		PoolPaused,
+		OperationDisabled,
+		AssetDisabled,
+		AOverMax,
+		RampTooShort,
+		AChangeTooLarge,
+		MintFeeTooHigh,
+		SwapFeeTooHigh,
+		RedeemFeeTooHigh,
+		AdminFeeTooHigh,
+		PoolAssetInUse,
+		NotMetaPool,
+		AssetNotFunded,
+		AssetNotWoundDown,
+		PoolDestroying,
+		PoolNotDestroying,
+		PoolNotDrained,
+		DeadlinePassed,
+		/// The input amount of an exact output swap could not be raised to cover the output amount
+		ExactOutputNotReached,
+		InvalidRate,
+		PoolNotEmpty,
+		AssetNotDrained,
 	}
 
-	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
-	pub struct MintResult<T: Config> {
-		pub mint_amount: T::Balance,
-		pub fee_amount: T::Balance,
-		pub balances: Vec<T::Balance>,
-		pub total_supply: T::Balance,
-	}
-
-	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
+	pub struct MintResult<Balance> {
+		pub mint_amount: Balance,
+		pub fee_amount: Balance,
+		pub lp_fee_amount: Balance,
+		pub balances: Vec<Balance>,
+		pub total_supply: Balance,
+	}
+
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
 	pub struct SwapResult<Balance> {
 		pub dx: Balance,
 		pub dy: Balance,
 		pub y: Balance,
 		pub balance_i: Balance,
+		/// The share of the swap fee sent to the fee recipient, in the output asset.
+		pub fee_amount: Balance,
+		/// The share of the swap fee kept for the liquidity providers, in the output asset.
+		pub lp_fee_amount: Balance,
 	}
 
-	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
 	pub struct RedeemProportionResult<Balance> {
 		pub amounts: Vec<Balance>,
 		pub balances: Vec<Balance>,
 		pub fee_amount: Balance,
+		pub lp_fee_amount: Balance,
 		pub total_supply: Balance,
 		pub redeem_amount: Balance,
 	}
 
-	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
-	pub struct RedeemSingleResult<T: Config> {
-		pub dy: T::Balance,
-		pub fee_amount: T::Balance,
-		pub total_supply: T::Balance,
-		pub balances: Vec<T::Balance>,
-		pub redeem_amount: T::Balance,
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
+	pub struct RedeemSingleResult<Balance> {
+		pub dy: Balance,
+		pub fee_amount: Balance,
+		pub lp_fee_amount: Balance,
+		pub total_supply: Balance,
+		pub balances: Vec<Balance>,
+		pub redeem_amount: Balance,
 	}
 
-	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
-	pub struct RedeemMultiResult<T: Config> {
-		pub redeem_amount: T::Balance,
-		pub fee_amount: T::Balance,
-		pub balances: Vec<T::Balance>,
-		pub total_supply: T::Balance,
-		pub burn_amount: T::Balance,
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug, TypeInfo)]
+	pub struct RedeemMultiResult<Balance> {
+		pub redeem_amount: Balance,
+		pub fee_amount: Balance,
+		pub lp_fee_amount: Balance,
+		pub balances: Vec<Balance>,
+		pub total_supply: Balance,
+		pub burn_amount: Balance,
 	}
 
 	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
 	pub struct PendingFeeResult<T: Config> {
 		pub fee_amount: T::Balance,
+		pub lp_fee_amount: T::Balance,
 		pub balances: Vec<T::Balance>,
 		pub total_supply: T::Balance,
+		pub pool_token_supply: T::Balance,
 	}
 
 	#[pallet::hooks]
-	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
+
+		fn integrity_test() {
+			// asset indexes are disabled with `u32` bit sets in `PoolRestrictions`
+			assert!(T::PoolAssetLimit::get() <= 32, "PoolAssetLimit must not exceed 32");
+			assert!(
+				T::MaxAChangeFactor::get() >= One::one(),
+				"MaxAChangeFactor must be at least 1"
+			);
+			let fee_precision = T::FeePrecision::get();
+			assert!(
+				T::MaxMintFee::get() < fee_precision,
+				"MaxMintFee must be below FeePrecision"
+			);
+			assert!(
+				T::MaxSwapFee::get() < fee_precision,
+				"MaxSwapFee must be below FeePrecision"
+			);
+			assert!(
+				T::MaxRedeemFee::get() < fee_precision,
+				"MaxRedeemFee must be below FeePrecision"
+			);
+		}
+	}
 
+	/// The user calls which predate deadlines keep their signatures. Each has a `_v2` variant taking an
+	/// optional `deadline`, the last block it may execute in, which rejects the call with
+	/// `DeadlinePassed` after it. Where the original call takes `asset_length`, its `_v2` variant derives
+	/// the size of the pool from storage instead.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		#[pallet::call_index(0)]
@@ -578,19 +1298,35 @@ pub mod pallet {
 		}
 
 		#[pallet::call_index(1)]
-		#[pallet::weight(T::WeightInfo::mint(amounts.len() as u32))]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::mint(amounts.len() as u32),
+			amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
 		pub fn mint(
 			origin: OriginFor<T>,
 			pool_id: StableAssetPoolId,
 			amounts: Vec<T::Balance>,
 			min_mint_amount: T::Balance,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			<Self as StableAsset>::mint(&who, pool_id, amounts, min_mint_amount)
+			let asset_length = amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::mint(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::mint(&who, pool_id, amounts, min_mint_amount),
+			)
 		}
 
 		#[pallet::call_index(2)]
-		#[pallet::weight(T::WeightInfo::swap(*asset_length))]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::swap(*asset_length),
+			*asset_length,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
 		pub fn swap(
 			origin: OriginFor<T>,
 			pool_id: StableAssetPoolId,
@@ -599,26 +1335,46 @@ pub mod pallet {
 			dx: T::Balance,
 			min_dy: T::Balance,
 			asset_length: u32,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			<Self as StableAsset>::swap(&who, pool_id, i, j, dx, min_dy, asset_length)?;
-			Ok(())
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::swap(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::swap(&who, pool_id, i, j, dx, min_dy, asset_length),
+			)
 		}
 
 		#[pallet::call_index(3)]
-		#[pallet::weight(T::WeightInfo::redeem_proportion(min_redeem_amounts.len() as u32))]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_proportion(min_redeem_amounts.len() as u32),
+			min_redeem_amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
 		pub fn redeem_proportion(
 			origin: OriginFor<T>,
 			pool_id: StableAssetPoolId,
 			amount: T::Balance,
 			min_redeem_amounts: Vec<T::Balance>,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			<Self as StableAsset>::redeem_proportion(&who, pool_id, amount, min_redeem_amounts)
+			let asset_length = min_redeem_amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_proportion(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_proportion(&who, pool_id, amount, min_redeem_amounts),
+			)
 		}
 
 		#[pallet::call_index(4)]
-		#[pallet::weight(T::WeightInfo::redeem_single(*asset_length))]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_single(*asset_length),
+			*asset_length,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
 		pub fn redeem_single(
 			origin: OriginFor<T>,
 			pool_id: StableAssetPoolId,
@@ -626,22 +1382,37 @@ pub mod pallet {
 			i: PoolTokenIndex,
 			min_redeem_amount: T::Balance,
 			asset_length: u32,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			<Self as StableAsset>::redeem_single(&who, pool_id, amount, i, min_redeem_amount, asset_length)?;
-			Ok(())
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_single(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_single(&who, pool_id, amount, i, min_redeem_amount, asset_length),
+			)
 		}
 
 		#[pallet::call_index(5)]
-		#[pallet::weight(T::WeightInfo::redeem_multi(amounts.len() as u32))]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_multi(amounts.len() as u32),
+			amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
 		pub fn redeem_multi(
 			origin: OriginFor<T>,
 			pool_id: StableAssetPoolId,
 			amounts: Vec<T::Balance>,
 			max_redeem_amount: T::Balance,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			<Self as StableAsset>::redeem_multi(&who, pool_id, amounts, max_redeem_amount)
+			let asset_length = amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_multi(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_multi(&who, pool_id, amounts, max_redeem_amount),
+			)
 		}
 
 		#[pallet::call_index(6)]
@@ -677,6 +1448,12 @@ pub mod pallet {
 				if let Some(fee) = redeem_fee {
 					pool_info.redeem_fee = fee;
 				}
+				Self::ensure_fees_valid(
+					pool_info.mint_fee,
+					pool_info.swap_fee,
+					pool_info.redeem_fee,
+					pool_info.offpeg_fee_multiplier,
+				)?;
 				Self::deposit_event(Event::FeeModified {
 					pool_id,
 					mint_fee: pool_info.mint_fee,
@@ -712,6 +1489,486 @@ pub mod pallet {
 				Ok(())
 			})
 		}
+
+		#[pallet::call_index(9)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			Pallet::<T>::swap_route_weight(route),
+			T::PoolAssetLimit::get(),
+			MAX_SOLVES_PER_ROUTE_STEP.saturating_mul(route.len() as u32),
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_route(
+			origin: OriginFor<T>,
+			route: Vec<SwapRouteStep>,
+			input_amount: T::Balance,
+			min_output_amount: T::Balance,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let weight = Self::swap_route_weight(&route);
+			Self::dispatch_with_newton_iterations(
+				weight,
+				T::PoolAssetLimit::get(),
+				MAX_SOLVES_PER_ROUTE_STEP.saturating_mul(route.len() as u32),
+				|| <Self as StableAsset>::swap_route(&who, route, input_amount, min_output_amount),
+			)
+		}
+
+		#[pallet::call_index(10)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::swap_exact_output(*asset_length),
+			*asset_length,
+			MAX_SOLVES_PER_EXACT_OUTPUT,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_exact_output(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dy: T::Balance,
+			max_dx: T::Balance,
+			asset_length: u32,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::swap_exact_output(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_EXACT_OUTPUT,
+				|| <Self as StableAsset>::swap_exact_output(&who, pool_id, i, j, dy, max_dx, asset_length),
+			)
+		}
+
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::set_pool_status())]
+		pub fn set_pool_status(origin: OriginFor<T>, pool_id: StableAssetPoolId, status: PoolStatus) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotFound);
+			ensure!(
+				status != PoolStatus::Destroying && PoolStatuses::<T>::get(pool_id) != PoolStatus::Destroying,
+				Error::<T>::PoolDestroying
+			);
+			PoolStatuses::<T>::insert(pool_id, status);
+			Self::deposit_event(Event::PoolStatusModified { pool_id, status });
+			Ok(())
+		}
+
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::set_paused())]
+		pub fn set_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			Paused::<T>::put(paused);
+			Self::deposit_event(Event::PausedModified { paused });
+			Ok(())
+		}
+
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::set_restrictions())]
+		pub fn set_restrictions(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			restrictions: PoolRestrictions,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotFound);
+			Restrictions::<T>::insert(pool_id, restrictions);
+			Self::deposit_event(Event::RestrictionsModified { pool_id, restrictions });
+			Ok(())
+		}
+
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::stop_ramp_a())]
+		pub fn stop_ramp_a(origin: OriginFor<T>, pool_id: StableAssetPoolId) -> DispatchResult {
+			T::ListingOrigin::ensure_origin(origin)?;
+			<Self as StableAsset>::stop_ramp_a(pool_id)
+		}
+
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::modify_offpeg_fee_multiplier())]
+		pub fn modify_offpeg_fee_multiplier(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			offpeg_fee_multiplier: T::AtLeast64BitUnsigned,
+		) -> DispatchResult {
+			T::ListingOrigin::ensure_origin(origin)?;
+			Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
+				let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+				Self::ensure_fees_valid(
+					pool_info.mint_fee,
+					pool_info.swap_fee,
+					pool_info.redeem_fee,
+					offpeg_fee_multiplier,
+				)?;
+				pool_info.offpeg_fee_multiplier = offpeg_fee_multiplier;
+				Self::deposit_event(Event::OffpegFeeMultiplierModified {
+					pool_id,
+					offpeg_fee_multiplier,
+				});
+				Ok(())
+			})
+		}
+
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::modify_admin_fee())]
+		pub fn modify_admin_fee(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			admin_fee: T::AtLeast64BitUnsigned,
+		) -> DispatchResult {
+			T::ListingOrigin::ensure_origin(origin)?;
+			ensure!(admin_fee <= T::FeePrecision::get(), Error::<T>::AdminFeeTooHigh);
+			Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
+				let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+				// fees pending so far are split at the old share, after the rate changes accrue to the
+				// liquidity providers
+				Self::collect_yield(pool_id, pool_info)?;
+				Self::collect_fee(pool_id, pool_info)?;
+				pool_info.admin_fee = admin_fee;
+				Self::deposit_event(Event::AdminFeeModified { pool_id, admin_fee });
+				Ok(())
+			})
+		}
+
+		/// Swap between the underlying assets of a metapool: its assets other than the base pool token,
+		/// followed by the assets of its base pool. Base pool assets are minted into or redeemed from the
+		/// base pool in the same call.
+		#[pallet::call_index(17)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			Pallet::<T>::swap_underlying_weight(),
+			T::PoolAssetLimit::get(),
+			2 * MAX_SOLVES_PER_ROUTE_STEP,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_underlying(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dx: T::Balance,
+			min_dy: T::Balance,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::dispatch_with_newton_iterations(
+				Self::swap_underlying_weight(),
+				T::PoolAssetLimit::get(),
+				2 * MAX_SOLVES_PER_ROUTE_STEP,
+				|| <Self as StableAsset>::swap_underlying(&who, pool_id, i, j, dx, min_dy),
+			)
+		}
+
+		/// Set the half-life of the EMA prices of a pool, in blocks.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::modify_ema_half_life())]
+		pub fn modify_ema_half_life(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			half_life: BlockNumberFor<T>,
+		) -> DispatchResult {
+			T::ListingOrigin::ensure_origin(origin)?;
+			ensure!(!half_life.is_zero(), Error::<T>::ArgumentsError);
+			ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotFound);
+			EmaHalfLives::<T>::insert(pool_id, half_life);
+			Self::deposit_event(Event::EmaHalfLifeModified { pool_id, half_life });
+			Ok(())
+		}
+
+		/// Append an asset to a pool. A pool holding liquidity takes the amount of the asset sent to its
+		/// account beforehand as its balance, minting the D it adds to the yield recipient. The amount
+		/// must be large enough for the pool to stay as balanced as before, or D would drop; an amount
+		/// worth an equal share of the pool is always enough. Charged for a pool of `PoolAssetLimit`
+		/// assets, refunding the weight of the assets the pool does not have.
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::add_asset(T::PoolAssetLimit::get()))]
+		#[allow(clippy::useless_conversion)]
+		pub fn add_asset(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			asset: T::AssetId,
+			precision: T::AtLeast64BitUnsigned,
+		) -> DispatchResultWithPostInfo {
+			T::ListingOrigin::ensure_origin(origin)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			<Self as StableAsset>::add_asset(pool_id, asset, precision)?;
+			Ok(Some(T::WeightInfo::add_asset(asset_length)).into())
+		}
+
+		/// Remove an asset from a pool. The asset must be wound down first by disabling it as an input
+		/// with `set_restrictions`, leaving only outbound swaps and redeems, until its normalised balance
+		/// is at most `AssetDustThreshold`. The dust left is swept to `DustAccount`, its value lost to
+		/// the liquidity providers. Charged for a pool of `PoolAssetLimit` assets, refunding the weight of
+		/// the assets the pool does not have.
+		#[pallet::call_index(20)]
+		#[pallet::weight(T::WeightInfo::remove_asset(T::PoolAssetLimit::get()))]
+		#[allow(clippy::useless_conversion)]
+		pub fn remove_asset(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			index: PoolTokenIndex,
+		) -> DispatchResultWithPostInfo {
+			T::ListingOrigin::ensure_origin(origin)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			<Self as StableAsset>::remove_asset(pool_id, index)?;
+			Ok(Some(T::WeightInfo::remove_asset(asset_length)).into())
+		}
+
+		/// Start destroying a pool: only redeems are allowed from now on, letting the liquidity
+		/// providers withdraw until `destroy_pool` can remove it. Charged for a pool of `PoolAssetLimit`
+		/// assets, refunding the weight of the assets the pool does not have.
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::start_destroy_pool(T::PoolAssetLimit::get()))]
+		#[allow(clippy::useless_conversion)]
+		pub fn start_destroy_pool(origin: OriginFor<T>, pool_id: StableAssetPoolId) -> DispatchResultWithPostInfo {
+			T::ListingOrigin::ensure_origin(origin)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			<Self as StableAsset>::start_destroy_pool(pool_id)?;
+			Ok(Some(T::WeightInfo::start_destroy_pool(asset_length)).into())
+		}
+
+		/// Remove a pool being destroyed once its pool token supply is at most `PoolDustThreshold`,
+		/// sweeping the assets left in its account to `DustAccount`. Charged for a pool of
+		/// `PoolAssetLimit` assets, refunding the weight of the assets the pool does not have.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::destroy_pool(T::PoolAssetLimit::get()))]
+		#[allow(clippy::useless_conversion)]
+		pub fn destroy_pool(origin: OriginFor<T>, pool_id: StableAssetPoolId) -> DispatchResultWithPostInfo {
+			T::ListingOrigin::ensure_origin(origin)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			<Self as StableAsset>::destroy_pool(pool_id)?;
+			Ok(Some(T::WeightInfo::destroy_pool(asset_length)).into())
+		}
+
+		/// `swap` without `asset_length` and with an optional `deadline`, the last block it may execute
+		/// in. Charged for a pool of `PoolAssetLimit` assets, refunding the weight of the assets the
+		/// pool does not have.
+		#[pallet::call_index(23)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::swap(T::PoolAssetLimit::get()),
+			T::PoolAssetLimit::get(),
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dx: T::Balance,
+			min_dy: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::swap(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::swap(&who, pool_id, i, j, dx, min_dy, asset_length),
+			)
+		}
+
+		/// `swap_exact_output` without `asset_length` and with an optional `deadline`, the last block it
+		/// may execute in. Charged for a pool of `PoolAssetLimit` assets, refunding the weight of the
+		/// assets the pool does not have.
+		#[pallet::call_index(24)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::swap_exact_output(T::PoolAssetLimit::get()),
+			T::PoolAssetLimit::get(),
+			MAX_SOLVES_PER_EXACT_OUTPUT,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_exact_output_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dy: T::Balance,
+			max_dx: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::swap_exact_output(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_EXACT_OUTPUT,
+				|| <Self as StableAsset>::swap_exact_output(&who, pool_id, i, j, dy, max_dx, asset_length),
+			)
+		}
+
+		/// `redeem_single` without `asset_length` and with an optional `deadline`, the last block it may
+		/// execute in. Charged for a pool of `PoolAssetLimit` assets, refunding the weight of the assets
+		/// the pool does not have.
+		#[pallet::call_index(25)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_single(T::PoolAssetLimit::get()),
+			T::PoolAssetLimit::get(),
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn redeem_single_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			amount: T::Balance,
+			i: PoolTokenIndex,
+			min_redeem_amount: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = Self::get_asset_length(pool_id)?;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_single(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_single(&who, pool_id, amount, i, min_redeem_amount, asset_length),
+			)
+		}
+
+		/// Make a pool a metapool of `base_pool_id`, valuing the pool token of the base pool it holds at
+		/// the virtual price of the base pool. The pool must not have any liquidity yet, so that no
+		/// liquidity is repriced.
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::set_base_pool())]
+		pub fn set_base_pool(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			base_pool_id: StableAssetPoolId,
+		) -> DispatchResult {
+			T::ListingOrigin::ensure_origin(origin)?;
+			<Self as StableAsset>::set_base_pool(pool_id, base_pool_id)
+		}
+
+		/// `mint` with an optional `deadline`, the last block it may execute in.
+		#[pallet::call_index(27)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::mint(amounts.len() as u32),
+			amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn mint_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			amounts: Vec<T::Balance>,
+			min_mint_amount: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::mint(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::mint(&who, pool_id, amounts, min_mint_amount),
+			)
+		}
+
+		/// `redeem_proportion` with an optional `deadline`, the last block it may execute in.
+		#[pallet::call_index(28)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_proportion(min_redeem_amounts.len() as u32),
+			min_redeem_amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn redeem_proportion_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			amount: T::Balance,
+			min_redeem_amounts: Vec<T::Balance>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = min_redeem_amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_proportion(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_proportion(&who, pool_id, amount, min_redeem_amounts),
+			)
+		}
+
+		/// `redeem_multi` with an optional `deadline`, the last block it may execute in.
+		#[pallet::call_index(29)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			T::WeightInfo::redeem_multi(amounts.len() as u32),
+			amounts.len() as u32,
+			MAX_SOLVES_PER_OPERATION,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn redeem_multi_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			amounts: Vec<T::Balance>,
+			max_redeem_amount: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let asset_length = amounts.len() as u32;
+			Self::dispatch_with_newton_iterations(
+				T::WeightInfo::redeem_multi(asset_length),
+				asset_length,
+				MAX_SOLVES_PER_OPERATION,
+				|| <Self as StableAsset>::redeem_multi(&who, pool_id, amounts, max_redeem_amount),
+			)
+		}
+
+		/// `swap_route` with an optional `deadline`, the last block it may execute in.
+		#[pallet::call_index(30)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			Pallet::<T>::swap_route_weight(route),
+			T::PoolAssetLimit::get(),
+			MAX_SOLVES_PER_ROUTE_STEP.saturating_mul(route.len() as u32),
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_route_v2(
+			origin: OriginFor<T>,
+			route: Vec<SwapRouteStep>,
+			input_amount: T::Balance,
+			min_output_amount: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			let weight = Self::swap_route_weight(&route);
+			Self::dispatch_with_newton_iterations(
+				weight,
+				T::PoolAssetLimit::get(),
+				MAX_SOLVES_PER_ROUTE_STEP.saturating_mul(route.len() as u32),
+				|| <Self as StableAsset>::swap_route(&who, route, input_amount, min_output_amount),
+			)
+		}
+
+		/// `swap_underlying` with an optional `deadline`, the last block it may execute in.
+		#[pallet::call_index(31)]
+		#[pallet::weight(Pallet::<T>::with_max_newton_iterations(
+			Pallet::<T>::swap_underlying_weight(),
+			T::PoolAssetLimit::get(),
+			2 * MAX_SOLVES_PER_ROUTE_STEP,
+		))]
+		#[allow(clippy::useless_conversion)]
+		pub fn swap_underlying_v2(
+			origin: OriginFor<T>,
+			pool_id: StableAssetPoolId,
+			i: PoolTokenIndex,
+			j: PoolTokenIndex,
+			dx: T::Balance,
+			min_dy: T::Balance,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+			Self::dispatch_with_newton_iterations(
+				Self::swap_underlying_weight(),
+				T::PoolAssetLimit::get(),
+				2 * MAX_SOLVES_PER_ROUTE_STEP,
+				|| <Self as StableAsset>::swap_underlying(&who, pool_id, i, j, dx, min_dy),
+			)
+		}
 	}
 }
 impl<T: Config> Pallet<T> {
@@ -772,7 +2029,9 @@ impl<T: Config> Pallet<T> {
 
 		let mut prev_d: U512;
 		let mut d: U512 = sum;
+		let mut iterations: u32 = 0;
 		for _i in 0..NUMBER_OF_ITERATIONS_TO_CONVERGE {
+			iterations = iterations.saturating_add(1);
 			let mut p_d: U512 = d;
 			for x in balances.iter() {
 				let balance: u128 = (*x).saturated_into::<u128>();
@@ -801,6 +2060,7 @@ impl<T: Config> Pallet<T> {
 				break;
 			}
 		}
+		Self::count_newton_iterations(iterations);
 		let result: u128 = u128::try_from(d).ok()?;
 		Some(result.into())
 	}
@@ -840,7 +2100,9 @@ impl<T: Config> Pallet<T> {
 		let mut prev_y: U512;
 		let mut y: U512 = target_d_u256;
 
+		let mut iterations: u32 = 0;
 		for _i in 0..NUMBER_OF_ITERATIONS_TO_CONVERGE {
+			iterations = iterations.saturating_add(1);
 			prev_y = y;
 			y = y
 				.checked_mul(y)?
@@ -854,32 +2116,553 @@ impl<T: Config> Pallet<T> {
 				break;
 			}
 		}
+		Self::count_newton_iterations(iterations);
 		let result: u128 = u128::try_from(y).ok()?;
 		Some(result.into())
 	}
 
-	pub(crate) fn get_mint_amount(
+	/// The swap fee at the (normalized) balances `xpi` and `xpj` of the input and output assets, as in
+	/// Curve's pools with an `offpeg_fee_multiplier`: `fee` when the two balances are equal, rising towards
+	/// `fee * offpeg_fee_multiplier / FeePrecision` as they diverge.
+	pub(crate) fn get_dynamic_fee(
+		xpi: T::AtLeast64BitUnsigned,
+		xpj: T::AtLeast64BitUnsigned,
+		fee: T::AtLeast64BitUnsigned,
+		offpeg_fee_multiplier: T::AtLeast64BitUnsigned,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
+		if offpeg_fee_multiplier <= fee_denominator {
+			return Some(fee);
+		}
+		let xpi: U512 = U512::from(xpi.saturated_into::<u128>());
+		let xpj: U512 = U512::from(xpj.saturated_into::<u128>());
+		let xps2: U512 = xpi.checked_add(xpj)?.checked_pow(U512::from(2u128))?;
+		if xps2.is_zero() {
+			return Some(fee);
+		}
+		let fee_denominator: U512 = U512::from(fee_denominator.saturated_into::<u128>());
+		let multiplier: U512 = U512::from(offpeg_fee_multiplier.saturated_into::<u128>());
+		let denominator: U512 = multiplier
+			.checked_sub(fee_denominator)?
+			.checked_mul(U512::from(4u128))?
+			.checked_mul(xpi)?
+			.checked_mul(xpj)?
+			.checked_div(xps2)?
+			.checked_add(fee_denominator)?;
+		let result: U512 = multiplier
+			.checked_mul(U512::from(fee.saturated_into::<u128>()))?
+			.checked_div(denominator)?;
+		let result: u128 = u128::try_from(result).ok()?;
+		Some(result.into())
+	}
+
+	/// `x * y / z` rounded down, or up if `round_up` is set.
+	pub(crate) fn mul_div(
+		x: T::AtLeast64BitUnsigned,
+		y: T::AtLeast64BitUnsigned,
+		z: T::AtLeast64BitUnsigned,
+		round_up: bool,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		let z: U512 = U512::from(z.saturated_into::<u128>());
+		if z.is_zero() {
+			return None;
+		}
+		let product: U512 =
+			U512::from(x.saturated_into::<u128>()).checked_mul(U512::from(y.saturated_into::<u128>()))?;
+		let mut result: U512 = product.checked_div(z)?;
+		if round_up && !product.checked_rem(z)?.is_zero() {
+			result = result.checked_add(U512::from(1u128))?;
+		}
+		let result: u128 = u128::try_from(result).ok()?;
+		Some(result.into())
+	}
+
+	/// The amount of pool token worth `amount` of D, in a pool of D `d` and `pool_token_supply` pool
+	/// tokens. Pool tokens are worth one D each in a pool without any.
+	pub(crate) fn d_to_pool_token(
+		amount: T::AtLeast64BitUnsigned,
+		d: T::AtLeast64BitUnsigned,
+		pool_token_supply: T::AtLeast64BitUnsigned,
+		round_up: bool,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		if d.is_zero() || pool_token_supply.is_zero() {
+			return Some(amount);
+		}
+		Self::mul_div(amount, pool_token_supply, d, round_up)
+	}
+
+	/// The D worth `amount` of pool token, in a pool of D `d` and `pool_token_supply` pool tokens.
+	pub(crate) fn pool_token_to_d(
+		amount: T::AtLeast64BitUnsigned,
+		d: T::AtLeast64BitUnsigned,
+		pool_token_supply: T::AtLeast64BitUnsigned,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		if pool_token_supply.is_zero() {
+			return Some(amount);
+		}
+		Self::mul_div(amount, d, pool_token_supply, false)
+	}
+
+	/// `pool_token_supply` after `amount` of pool token is burned.
+	pub(crate) fn sub_pool_token_supply(
+		pool_token_supply: T::Balance,
+		amount: T::Balance,
+	) -> Result<T::Balance, Error<T>> {
+		let pool_token_supply: T::AtLeast64BitUnsigned = pool_token_supply.into();
+		Ok(pool_token_supply
+			.checked_sub(&amount.into())
+			.ok_or(Error::<T>::Math)?
+			.into())
+	}
+
+	/// Split `fee_amount` into the share sent to the fee recipient and the share kept for the liquidity
+	/// providers.
+	pub(crate) fn split_fee(
+		fee_amount: T::AtLeast64BitUnsigned,
+		admin_fee: T::AtLeast64BitUnsigned,
+	) -> Option<(T::AtLeast64BitUnsigned, T::AtLeast64BitUnsigned)> {
+		let admin_fee_amount = Self::mul_div(fee_amount, admin_fee, T::FeePrecision::get(), false)?;
+		Some((admin_fee_amount, fee_amount.checked_sub(&admin_fee_amount)?))
+	}
+
+	/// The rates of the assets of a pool, in `RatePrecision` units. The base pool token of a metapool is
+	/// valued at the virtual price of its base pool, the D per pool token, and any other asset at the
+	/// rate of `RateProvider`. Assets without a rate are valued at one, and a zero rate is rejected.
+	pub(crate) fn get_rates(
 		pool_info: &StableAssetPoolInfo<
 			T::AssetId,
 			T::AtLeast64BitUnsigned,
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
-		amounts_bal: &[T::Balance],
-	) -> Result<MintResult<T>, Error<T>> {
-		// update pool balances and total supply to avoid stale data
-		let pool_info = Self::get_balance_update_amount(pool_info)?;
-		let pool_info = Self::get_collect_yield_amount(&pool_info)?;
-
-		if pool_info.balances.len() != amounts_bal.len() {
-			return Err(Error::<T>::ArgumentsMismatch);
-		}
+	) -> Result<Vec<T::AtLeast64BitUnsigned>, Error<T>> {
+		let base_pool = Self::get_base_pool(pool_info)
+			.and_then(|(index, base_pool_id)| Some((index as usize, Pools::<T>::get(base_pool_id)?)));
+		pool_info
+			.assets
+			.iter()
+			.enumerate()
+			.map(|(index, asset)| {
+				let rate = match &base_pool {
+					Some((base_index, base_pool_info)) if *base_index == index => {
+						Self::get_pool_virtual_price(base_pool_info, T::RatePrecision::get())
+					}
+					_ => T::RateProvider::get_rate(*asset),
+				}
+				.unwrap_or_else(T::RatePrecision::get);
+				ensure!(!rate.is_zero(), Error::<T>::InvalidRate);
+				Ok(rate)
+			})
+			.collect()
+	}
 
-		let amounts = Self::convert_vec_balance_to_number(amounts_bal.to_vec());
-		let a: T::AtLeast64BitUnsigned = Self::get_a(
-			pool_info.a,
-			pool_info.a_block,
+	/// The virtual price of a pool, the D per pool token, in `scale` units. Pool tokens are worth one D
+	/// each in a pool without any.
+	pub(crate) fn get_pool_virtual_price(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		scale: T::AtLeast64BitUnsigned,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		if pool_info.pool_token_supply.is_zero() {
+			return Some(scale);
+		}
+		Self::mul_div(
+			pool_info.total_supply.into(),
+			scale,
+			pool_info.pool_token_supply.into(),
+			false,
+		)
+	}
+
+	/// The marginal price of each asset of a pool in its first asset, in `scale` units: the amount of
+	/// the first asset a swap of an infinitesimal amount of the asset would give, before fees.
+	///
+	/// With `c = D^(n+1) / (n^n * prod(x))`, the invariant changes by `Ann + c / x_i` per unit of the
+	/// balance `x_i`, so the price of asset `i` is `(Ann + c / x_i) / (Ann + c / x_0)`, taken out of the
+	/// balances of the invariant with the precisions and rates of both assets.
+	pub(crate) fn get_marginal_prices(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		scale: T::AtLeast64BitUnsigned,
+	) -> Option<Vec<T::AtLeast64BitUnsigned>> {
+		let to_u512 = |x: T::AtLeast64BitUnsigned| U512::from(x.saturated_into::<u128>());
+		let a: T::AtLeast64BitUnsigned = Self::get_a(
+			pool_info.a,
+			pool_info.a_block,
+			pool_info.future_a,
+			pool_info.future_a_block,
+		)?;
+		let balances: Vec<U512> = pool_info
+			.balances
+			.iter()
+			.map(|balance| to_u512((*balance).into()))
+			.collect();
+		let d: U512 = to_u512(pool_info.total_supply.into());
+		let balance_size: U512 = U512::from(balances.len());
+		let a_precision: U512 = to_u512(T::APrecision::get());
+		let mut ann: U512 = to_u512(a);
+		let mut c: U512 = d;
+		for balance in balances.iter() {
+			ann = ann.checked_mul(balance_size)?;
+			c = c.checked_mul(d)?.checked_div(balance.checked_mul(balance_size)?)?;
+		}
+		let rates = Self::get_rates(pool_info).ok()?;
+		let x_0: U512 = *balances.first()?;
+		let unit_0: U512 = to_u512(*pool_info.precisions.first()?).checked_mul(to_u512(*rates.first()?))?;
+		let mut prices: Vec<T::AtLeast64BitUnsigned> = Vec::new();
+		for (i, x_i) in balances.iter().enumerate() {
+			let unit_i: U512 = to_u512(pool_info.precisions[i]).checked_mul(to_u512(rates[i]))?;
+			let ann_term: U512 = ann.checked_mul(*x_i)?.checked_mul(x_0)?.checked_div(a_precision)?;
+			let numerator: U512 = ann_term.checked_add(c.checked_mul(x_0)?)?;
+			let denominator: U512 = ann_term.checked_add(c.checked_mul(*x_i)?)?;
+			let price: U512 = numerator
+				.checked_mul(to_u512(scale))?
+				.checked_mul(unit_i)?
+				.checked_div(denominator.checked_mul(unit_0)?)?;
+			prices.push(u128::try_from(price).ok()?.into());
+		}
+		Some(prices)
+	}
+
+	/// Update the price oracle of a pool with the state left by a pool operation. Blocks since the last
+	/// update are priced at the prices of the last update, and the first update of a block records an
+	/// observation. The oracle never fails an operation: pools it cannot price, such as empty pools, keep
+	/// their previous prices.
+	pub(crate) fn update_oracle(
+		pool_id: StableAssetPoolId,
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+	) {
+		let update = || -> Option<()> {
+			let current_block = frame_system::Pallet::<T>::block_number();
+			let prices: BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>> =
+				Self::get_marginal_prices(pool_info, pool_info.precision)?
+					.try_into()
+					.ok()?;
+			let virtual_price = Self::get_pool_virtual_price(pool_info, pool_info.precision)?;
+			let mut oracle = match Oracles::<T>::get(pool_id) {
+				Some(mut oracle) if oracle.prices.len() == prices.len() => {
+					if current_block > oracle.block {
+						let elapsed = T::AtLeast64BitUnsigned::from(current_block.checked_sub(&oracle.block)?);
+						for (cumulative, price) in oracle.price_cumulatives.iter_mut().zip(oracle.prices.iter()) {
+							*cumulative = cumulative.checked_add(&price.checked_mul(&elapsed)?)?;
+						}
+						oracle.virtual_price_cumulative = oracle
+							.virtual_price_cumulative
+							.checked_add(&oracle.virtual_price.checked_mul(&elapsed)?)?;
+						if let Some(half_life) = EmaHalfLives::<T>::get(pool_id) {
+							let ema_prices = Self::get_pool_ema_prices(pool_id, &oracle, half_life, current_block)?;
+							EmaPrices::<T>::insert(pool_id, ema_prices);
+						}
+						oracle.block = current_block;
+						oracle.observation_index = oracle
+							.observation_index
+							.checked_add(1)?
+							.checked_rem(T::MaxOracleObservations::get())?;
+						Self::record_oracle_observation(pool_id, &oracle);
+					}
+					oracle
+				}
+				_ => {
+					let _ = OracleObservations::<T>::clear_prefix(pool_id, u32::MAX, None);
+					EmaPrices::<T>::remove(pool_id);
+					let oracle = PoolOracle {
+						block: current_block,
+						prices: prices.clone(),
+						virtual_price,
+						price_cumulatives: BoundedVec::truncate_from(sp_std::vec![Zero::zero(); prices.len()]),
+						virtual_price_cumulative: Zero::zero(),
+						observation_index: 0,
+					};
+					Self::record_oracle_observation(pool_id, &oracle);
+					oracle
+				}
+			};
+			oracle.prices = prices;
+			oracle.virtual_price = virtual_price;
+			Oracles::<T>::insert(pool_id, oracle);
+			Some(())
+		};
+		let _ = update();
+	}
+
+	fn record_oracle_observation(
+		pool_id: StableAssetPoolId,
+		oracle: &PoolOracle<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>,
+	) {
+		OracleObservations::<T>::insert(
+			pool_id,
+			oracle.observation_index,
+			OracleObservation {
+				block: oracle.block,
+				price_cumulatives: oracle.price_cumulatives.clone(),
+				virtual_price_cumulative: oracle.virtual_price_cumulative,
+			},
+		);
+	}
+
+	/// The EMA prices of a pool at `block`: the stored EMA prices, or the oracle prices if there are none
+	/// yet, moved towards the oracle prices by the blocks since the last oracle update.
+	pub(crate) fn get_pool_ema_prices(
+		pool_id: StableAssetPoolId,
+		oracle: &PoolOracle<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>,
+		half_life: BlockNumberFor<T>,
+		block: BlockNumberFor<T>,
+	) -> Option<BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>>> {
+		let elapsed = block.checked_sub(&oracle.block)?;
+		let ema_weight = Self::get_ema_weight(elapsed.saturated_into::<u128>(), half_life.saturated_into::<u128>())?;
+		let ema_weight: T::AtLeast64BitUnsigned = ema_weight.into();
+		let ema_precision: T::AtLeast64BitUnsigned = EMA_PRECISION.into();
+		let price_weight = ema_precision.checked_sub(&ema_weight)?;
+		let ema_prices = EmaPrices::<T>::get(pool_id)
+			.filter(|ema_prices| ema_prices.len() == oracle.prices.len())
+			.unwrap_or_else(|| oracle.prices.clone());
+		let ema_prices = ema_prices
+			.iter()
+			.zip(oracle.prices.iter())
+			.map(|(ema_price, price)| {
+				Self::mul_div(*ema_price, ema_weight, ema_precision, false)?.checked_add(&Self::mul_div(
+					*price,
+					price_weight,
+					ema_precision,
+					false,
+				)?)
+			})
+			.collect::<Option<Vec<_>>>()?;
+		ema_prices.try_into().ok()
+	}
+
+	/// The weight `2^(-elapsed / half_life)` of the previous EMA after `elapsed` blocks, in
+	/// `EMA_PRECISION` units. The fraction of a half-life is taken as `exp(-x)` with `x < ln 2`, summing
+	/// its Taylor series until the terms vanish.
+	pub(crate) fn get_ema_weight(elapsed: u128, half_life: u128) -> Option<u128> {
+		let halvings = elapsed.checked_div(half_life)?;
+		if halvings >= 64 {
+			return Some(0);
+		}
+		let x = elapsed
+			.checked_rem(half_life)?
+			.checked_mul(LN_2)?
+			.checked_div(half_life)?;
+		let mut weight: u128 = EMA_PRECISION;
+		let mut term: u128 = EMA_PRECISION;
+		let mut k: u128 = 1;
+		while !term.is_zero() {
+			term = term.checked_mul(x)?.checked_div(k.checked_mul(EMA_PRECISION)?)?;
+			weight = if k % 2 == 1 {
+				weight.checked_sub(term)?
+			} else {
+				weight.checked_add(term)?
+			};
+			k = k.checked_add(1)?;
+		}
+		Some(weight >> halvings)
+	}
+
+	/// The cumulative prices of the oracle of a pool at `block`, interpolated between the observations
+	/// around it. `None` if `block` is before the oldest observation.
+	pub(crate) fn get_oracle_cumulatives(
+		pool_id: StableAssetPoolId,
+		oracle: &PoolOracle<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>,
+		block: BlockNumberFor<T>,
+	) -> Option<(Vec<T::AtLeast64BitUnsigned>, T::AtLeast64BitUnsigned)> {
+		if block >= oracle.block {
+			// priced at the prices of the last update since
+			let elapsed = T::AtLeast64BitUnsigned::from(block.checked_sub(&oracle.block)?);
+			let prices = oracle
+				.price_cumulatives
+				.iter()
+				.zip(oracle.prices.iter())
+				.map(|(cumulative, price)| cumulative.checked_add(&price.checked_mul(&elapsed)?))
+				.collect::<Option<Vec<_>>>()?;
+			let virtual_price = oracle
+				.virtual_price_cumulative
+				.checked_add(&oracle.virtual_price.checked_mul(&elapsed)?)?;
+			return Some((prices, virtual_price));
+		}
+
+		// the last observation at or before `block` and the first one after it
+		let mut before: Option<OracleObservation<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>> =
+			None;
+		let mut after: Option<OracleObservation<T::AtLeast64BitUnsigned, BlockNumberFor<T>, PoolAssetLimitOf<T>>> =
+			None;
+		for observation in OracleObservations::<T>::iter_prefix_values(pool_id) {
+			if observation.block <= block {
+				if before.as_ref().is_none_or(|before| observation.block > before.block) {
+					before = Some(observation);
+				}
+			} else if after.as_ref().is_none_or(|after| observation.block < after.block) {
+				after = Some(observation);
+			}
+		}
+		let before = before?;
+		if before.block == block {
+			return Some((before.price_cumulatives.to_vec(), before.virtual_price_cumulative));
+		}
+		// the prices are constant between two observations
+		let after = after?;
+		let elapsed = T::AtLeast64BitUnsigned::from(block.checked_sub(&before.block)?);
+		let span = T::AtLeast64BitUnsigned::from(after.block.checked_sub(&before.block)?);
+		let interpolate = |start: T::AtLeast64BitUnsigned, end: T::AtLeast64BitUnsigned| {
+			start.checked_add(&Self::mul_div(end.checked_sub(&start)?, elapsed, span, false)?)
+		};
+		let prices = before
+			.price_cumulatives
+			.iter()
+			.zip(after.price_cumulatives.iter())
+			.map(|(start, end)| interpolate(*start, *end))
+			.collect::<Option<Vec<_>>>()?;
+		let virtual_price = interpolate(before.virtual_price_cumulative, after.virtual_price_cumulative)?;
+		Some((prices, virtual_price))
+	}
+
+	/// The index of the base pool token in a metapool and the id of its base pool, see `set_base_pool`.
+	pub(crate) fn get_base_pool(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+	) -> Option<(PoolTokenIndex, StableAssetPoolId)> {
+		let base_pool_id = PoolAssetPools::<T>::get(pool_info.pool_asset).and_then(BasePools::<T>::get)?;
+		let base_pool_asset = Pools::<T>::get(base_pool_id)?.pool_asset;
+		let index = pool_info.assets.iter().position(|asset| *asset == base_pool_asset)?;
+		Some((index as PoolTokenIndex, base_pool_id))
+	}
+
+	/// The route swapping the underlying asset at `i` of a metapool into the one at `j`, see
+	/// `swap_underlying`.
+	pub(crate) fn get_underlying_route(
+		pool_id: StableAssetPoolId,
+		i: PoolTokenIndex,
+		j: PoolTokenIndex,
+	) -> Result<Vec<SwapRouteStep>, Error<T>> {
+		ensure!(i != j, Error::<T>::ArgumentsError);
+		let pool_info = Pools::<T>::get(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+		let (base_index, base_pool_id) = Self::get_base_pool(&pool_info).ok_or(Error::<T>::NotMetaPool)?;
+		let base_pool_info = Pools::<T>::get(base_pool_id).ok_or(Error::<T>::PoolNotFound)?;
+		let meta_length = pool_info.assets.len().saturating_sub(1) as PoolTokenIndex;
+		let base_length = base_pool_info.assets.len() as PoolTokenIndex;
+		ensure!(
+			i < meta_length.saturating_add(base_length) && j < meta_length.saturating_add(base_length),
+			Error::<T>::ArgumentsError
+		);
+		// the index in the metapool of an underlying asset of the metapool, skipping the base pool token
+		let meta_index = |index: PoolTokenIndex| if index < base_index { index } else { index + 1 };
+		let route = match (i < meta_length, j < meta_length) {
+			(true, true) => sp_std::vec![SwapRouteStep::Swap {
+				pool_id,
+				input_index: meta_index(i),
+				output_index: meta_index(j),
+			}],
+			(true, false) => sp_std::vec![
+				SwapRouteStep::Swap {
+					pool_id,
+					input_index: meta_index(i),
+					output_index: base_index,
+				},
+				SwapRouteStep::RedeemSingle {
+					pool_id: base_pool_id,
+					output_index: j - meta_length,
+				},
+			],
+			(false, true) => sp_std::vec![
+				SwapRouteStep::Mint {
+					pool_id: base_pool_id,
+					input_index: i - meta_length,
+				},
+				SwapRouteStep::Swap {
+					pool_id,
+					input_index: base_index,
+					output_index: meta_index(j),
+				},
+			],
+			(false, false) => sp_std::vec![SwapRouteStep::Swap {
+				pool_id: base_pool_id,
+				input_index: i - meta_length,
+				output_index: j - meta_length,
+			}],
+		};
+		Ok(route)
+	}
+
+	/// `amount` of a pool asset as a balance of the invariant: scaled by `precision` and valued at
+	/// `rate`.
+	pub(crate) fn normalize(
+		amount: T::AtLeast64BitUnsigned,
+		precision: T::AtLeast64BitUnsigned,
+		rate: T::AtLeast64BitUnsigned,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		let amount = amount.checked_mul(&precision)?;
+		let rate_precision = T::RatePrecision::get();
+		if rate == rate_precision {
+			return Some(amount);
+		}
+		Self::mul_div(amount, rate, rate_precision, false)
+	}
+
+	/// The amount of a pool asset worth `amount` of invariant balance, the inverse of `normalize`
+	/// rounded down.
+	pub(crate) fn denormalize(
+		amount: T::AtLeast64BitUnsigned,
+		precision: T::AtLeast64BitUnsigned,
+		rate: T::AtLeast64BitUnsigned,
+	) -> Option<T::AtLeast64BitUnsigned> {
+		let rate_precision = T::RatePrecision::get();
+		let amount = if rate == rate_precision {
+			amount
+		} else {
+			Self::mul_div(amount, rate_precision, rate, false)?
+		};
+		amount.checked_div(&precision)
+	}
+
+	pub(crate) fn get_mint_amount(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		amounts_bal: &[T::Balance],
+	) -> Result<MintResult<T::Balance>, Error<T>> {
+		// update pool balances and total supply to avoid stale data
+		let pool_info = Self::get_balance_update_amount(pool_info)?;
+		let pool_info = Self::get_collect_yield_amount(&pool_info)?;
+
+		if pool_info.balances.len() != amounts_bal.len() {
+			return Err(Error::<T>::ArgumentsMismatch);
+		}
+
+		let amounts = Self::convert_vec_balance_to_number(amounts_bal.to_vec());
+		let a: T::AtLeast64BitUnsigned = Self::get_a(
+			pool_info.a,
+			pool_info.a_block,
 			pool_info.future_a,
 			pool_info.future_a_block,
 		)
@@ -888,8 +2671,9 @@ impl<T: Config> Pallet<T> {
 		let zero: T::AtLeast64BitUnsigned = Zero::zero();
 		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
 
+		let rates = Self::get_rates(&pool_info)?;
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		for i in 0..balances.len() {
 			if amounts[i] == zero {
 				if old_d == zero {
@@ -898,17 +2682,14 @@ impl<T: Config> Pallet<T> {
 				continue;
 			}
 			let result: T::AtLeast64BitUnsigned = balances[i]
-				.checked_add(
-					&amounts[i]
-						.checked_mul(&pool_info.precisions[i])
-						.ok_or(Error::<T>::Math)?,
-				)
+				.checked_add(&Self::normalize(amounts[i], pool_info.precisions[i], rates[i]).ok_or(Error::<T>::Math)?)
 				.ok_or(Error::<T>::Math)?;
 			balances[i] = result;
 		}
 		let new_d: T::AtLeast64BitUnsigned = Self::get_d(&balances, a).ok_or(Error::<T>::Math)?;
 		let mut mint_amount: T::AtLeast64BitUnsigned = new_d.checked_sub(&old_d).ok_or(Error::<T>::Math)?;
 		let mut fee_amount: T::AtLeast64BitUnsigned = zero;
+		let mut lp_fee_amount: T::AtLeast64BitUnsigned = zero;
 		let mint_fee: T::AtLeast64BitUnsigned = pool_info.mint_fee;
 
 		if pool_info.mint_fee > zero {
@@ -918,11 +2699,23 @@ impl<T: Config> Pallet<T> {
 				.checked_div(&fee_denominator)
 				.ok_or(Error::<T>::Math)?;
 			mint_amount = mint_amount.checked_sub(&fee_amount).ok_or(Error::<T>::Math)?;
+			(fee_amount, lp_fee_amount) = Self::split_fee(fee_amount, pool_info.admin_fee).ok_or(Error::<T>::Math)?;
 		}
 
+		// the share of the fee kept for the liquidity providers goes to the pool before the pool tokens are
+		// minted at its price
+		let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
+		let held_d: T::AtLeast64BitUnsigned = old_d.checked_add(&lp_fee_amount).ok_or(Error::<T>::Math)?;
+		let to_pool_token = |amount: T::AtLeast64BitUnsigned| -> Result<T::AtLeast64BitUnsigned, Error<T>> {
+			Self::d_to_pool_token(amount, held_d, pool_token_supply, false).ok_or(Error::<T>::Math)
+		};
+		let lp_fee_amount =
+			Self::d_to_pool_token(lp_fee_amount, old_d, pool_token_supply, false).ok_or(Error::<T>::Math)?;
+
 		Ok(MintResult {
-			mint_amount: mint_amount.into(),
-			fee_amount: fee_amount.into(),
+			mint_amount: to_pool_token(mint_amount)?.into(),
+			fee_amount: to_pool_token(fee_amount)?.into(),
+			lp_fee_amount: lp_fee_amount.into(),
 			balances: Self::convert_vec_number_to_balance(balances),
 			total_supply: new_d.into(),
 		})
@@ -935,6 +2728,7 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 		input_index: PoolTokenIndex,
 		output_index: PoolTokenIndex,
@@ -972,35 +2766,59 @@ impl<T: Config> Pallet<T> {
 		.ok_or(Error::<T>::Math)?;
 		let d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
 		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
+		let two: T::AtLeast64BitUnsigned = 2u8.into();
+		let rates = Self::get_rates(&pool_info)?;
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
+		let old_balance_i = balances[input_index_usize];
 		balances[input_index_usize] = balances[input_index_usize]
 			.checked_add(
-				&dx.checked_mul(&pool_info.precisions[input_index_usize])
+				&Self::normalize(dx, pool_info.precisions[input_index_usize], rates[input_index_usize])
 					.ok_or(Error::<T>::Math)?,
 			)
 			.ok_or(Error::<T>::Math)?;
 		let y: T::AtLeast64BitUnsigned = Self::get_y(&balances, output_index, d, a).ok_or(Error::<T>::Math)?;
-		let mut dy: T::AtLeast64BitUnsigned = balances[output_index_usize]
-			.checked_sub(&y)
-			.ok_or(Error::<T>::Math)?
-			.checked_sub(&one)
-			.ok_or(Error::<T>::Math)?
-			.checked_div(&pool_info.precisions[output_index_usize])
-			.ok_or(Error::<T>::Math)?;
-		if pool_info.swap_fee > zero {
-			let fee_amount: T::AtLeast64BitUnsigned = dy
-				.checked_mul(&pool_info.swap_fee)
+		let mut dy: T::AtLeast64BitUnsigned = Self::denormalize(
+			balances[output_index_usize]
+				.checked_sub(&y)
+				.ok_or(Error::<T>::Math)?
+				.checked_sub(&one)
+				.ok_or(Error::<T>::Math)?,
+			pool_info.precisions[output_index_usize],
+			rates[output_index_usize],
+		)
+		.ok_or(Error::<T>::Math)?;
+		// the fee is taken at the average of the balances before and after the swap
+		let swap_fee: T::AtLeast64BitUnsigned = Self::get_dynamic_fee(
+			old_balance_i
+				.checked_add(&balances[input_index_usize])
+				.and_then(|x| x.checked_div(&two))
+				.ok_or(Error::<T>::Math)?,
+			balances[output_index_usize]
+				.checked_add(&y)
+				.and_then(|x| x.checked_div(&two))
+				.ok_or(Error::<T>::Math)?,
+			pool_info.swap_fee,
+			pool_info.offpeg_fee_multiplier,
+		)
+		.ok_or(Error::<T>::Math)?;
+		let mut fee_amount: T::AtLeast64BitUnsigned = zero;
+		if swap_fee > zero {
+			fee_amount = dy
+				.checked_mul(&swap_fee)
 				.ok_or(Error::<T>::Math)?
 				.checked_div(&fee_denominator)
 				.ok_or(Error::<T>::Math)?;
 			dy = dy.checked_sub(&fee_amount).ok_or(Error::<T>::Math)?;
 		}
+		let (fee_amount, lp_fee_amount) = Self::split_fee(fee_amount, pool_info.admin_fee).ok_or(Error::<T>::Math)?;
 		Ok(SwapResult {
 			dx: dx_bal,
 			dy: dy.into(),
 			y: y.into(),
 			balance_i: balances[input_index_usize].into(),
+			fee_amount: fee_amount.into(),
+			lp_fee_amount: lp_fee_amount.into(),
 		})
 	}
 
@@ -1011,6 +2829,7 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 		input_index: PoolTokenIndex,
 		output_index: PoolTokenIndex,
@@ -1023,7 +2842,7 @@ impl<T: Config> Pallet<T> {
 		let zero: T::AtLeast64BitUnsigned = Zero::zero();
 		let one: T::AtLeast64BitUnsigned = One::one();
 		let balance_size: usize = pool_info.balances.len();
-		let mut dy: T::AtLeast64BitUnsigned = dy_bal.into();
+		let dy: T::AtLeast64BitUnsigned = dy_bal.into();
 		let input_index_usize = input_index as usize;
 		let output_index_usize = output_index as usize;
 		if input_index == output_index {
@@ -1040,10 +2859,7 @@ impl<T: Config> Pallet<T> {
 		}
 		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
 		let swap_exact_over_amount = T::SwapExactOverAmount::get();
-		if pool_info.swap_fee > zero {
-			let diff = fee_denominator.checked_sub(&pool_info.swap_fee)?;
-			dy = dy.checked_mul(&fee_denominator)?.checked_div(&diff)?;
-		}
+		let two: T::AtLeast64BitUnsigned = 2u8.into();
 
 		let a: T::AtLeast64BitUnsigned = Self::get_a(
 			pool_info.a,
@@ -1052,23 +2868,88 @@ impl<T: Config> Pallet<T> {
 			pool_info.future_a_block,
 		)?;
 		let d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
-		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
-		balances[output_index_usize] =
-			balances[output_index_usize].checked_sub(&dy.checked_mul(&pool_info.precisions[output_index_usize])?)?;
-		let y: T::AtLeast64BitUnsigned = Self::get_y(&balances, input_index, d, a)?;
-		let dx: T::AtLeast64BitUnsigned = y
-			.checked_sub(&balances[input_index_usize])?
-			.checked_sub(&one)?
-			.checked_div(&pool_info.precisions[input_index_usize])?
-			.checked_add(&swap_exact_over_amount)?;
-
-		Some(SwapResult {
+		let rates = Self::get_rates(&pool_info).ok()?;
+		let old_balances: Vec<T::AtLeast64BitUnsigned> =
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
+		// the balances after the swap, the new input balance and the output amount before the fee, if the swap is
+		// charged `swap_fee`
+		let quote = |swap_fee: T::AtLeast64BitUnsigned| -> Option<(
+			Vec<T::AtLeast64BitUnsigned>,
+			T::AtLeast64BitUnsigned,
+			T::AtLeast64BitUnsigned,
+		)> {
+			let mut dy = dy;
+			if swap_fee > zero {
+				let diff = fee_denominator.checked_sub(&swap_fee)?;
+				dy = dy.checked_mul(&fee_denominator)?.checked_div(&diff)?;
+			}
+			let mut balances = old_balances.clone();
+			balances[output_index_usize] = balances[output_index_usize].checked_sub(&Self::normalize(
+				dy,
+				pool_info.precisions[output_index_usize],
+				rates[output_index_usize],
+			)?)?;
+			let y: T::AtLeast64BitUnsigned = Self::get_y(&balances, input_index, d, a)?;
+			Some((balances, y, dy))
+		};
+		let swap_fee = Self::get_dynamic_fee(
+			old_balances[input_index_usize],
+			old_balances[output_index_usize],
+			pool_info.swap_fee,
+			pool_info.offpeg_fee_multiplier,
+		)?;
+		let (mut balances, mut y, mut gross_dy) = quote(swap_fee)?;
+		if pool_info.offpeg_fee_multiplier > fee_denominator {
+			// the swap is charged the fee at the average of the balances before and after it, so quote again
+			// at the fee of the first quote
+			let swap_fee = Self::get_dynamic_fee(
+				old_balances[input_index_usize].checked_add(&y)?.checked_div(&two)?,
+				old_balances[output_index_usize]
+					.checked_add(&balances[output_index_usize])?
+					.checked_div(&two)?,
+				pool_info.swap_fee,
+				pool_info.offpeg_fee_multiplier,
+			)?;
+			(balances, y, gross_dy) = quote(swap_fee)?;
+		}
+		let mut dx: T::AtLeast64BitUnsigned = Self::denormalize(
+			y.checked_sub(&balances[input_index_usize])?.checked_sub(&one)?,
+			pool_info.precisions[input_index_usize],
+			rates[input_index_usize],
+		)?
+		.checked_add(&swap_exact_over_amount)?;
+		let (fee_amount, lp_fee_amount) = Self::split_fee(gross_dy.checked_sub(&dy)?, pool_info.admin_fee)?;
+		let mut result = SwapResult {
 			dx: dx.into(),
 			dy: dy_bal,
 			y: y.into(),
 			balance_i: balances[input_index_usize].into(),
-		})
+			fee_amount: fee_amount.into(),
+			lp_fee_amount: lp_fee_amount.into(),
+		};
+
+		if pool_info.offpeg_fee_multiplier > fee_denominator {
+			// the dynamic fee of the second quote is an estimate, raise dx in proportion to the shortfall until
+			// the swap covers dy
+			for _ in 0..MAX_EXACT_OUTPUT_ADJUSTMENTS {
+				let swap_result = Self::get_swap_amount(&pool_info, input_index, output_index, dx.into()).ok()?;
+				let actual_dy: T::AtLeast64BitUnsigned = swap_result.dy.into();
+				if actual_dy >= dy {
+					break;
+				}
+				dx = dx
+					.checked_add(&dy.checked_sub(&actual_dy)?.checked_mul(&dx)?.checked_div(&actual_dy)?)?
+					.checked_add(&one)?
+					.checked_add(&swap_exact_over_amount)?;
+				result = SwapResult {
+					dx: dx.into(),
+					dy: dy_bal,
+					..swap_result
+				};
+			}
+		}
+
+		Some(result)
 	}
 
 	pub(crate) fn get_redeem_proportion_amount(
@@ -1078,6 +2959,7 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 		amount_bal: T::Balance,
 	) -> Result<RedeemProportionResult<T::Balance>, Error<T>> {
@@ -1093,12 +2975,14 @@ impl<T: Config> Pallet<T> {
 		}
 
 		let d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
+		let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
 		let mut amounts: Vec<T::AtLeast64BitUnsigned> = Vec::new();
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
 
 		let mut fee_amount: T::AtLeast64BitUnsigned = zero;
+		let mut lp_fee_amount: T::AtLeast64BitUnsigned = zero;
 		if pool_info.redeem_fee > zero {
 			fee_amount = amount
 				.checked_mul(&pool_info.redeem_fee)
@@ -1107,27 +2991,34 @@ impl<T: Config> Pallet<T> {
 				.ok_or(Error::<T>::Math)?;
 			// Redemption fee is charged with pool token before redemption.
 			amount = amount.checked_sub(&fee_amount).ok_or(Error::<T>::Math)?;
+			(fee_amount, lp_fee_amount) = Self::split_fee(fee_amount, pool_info.admin_fee).ok_or(Error::<T>::Math)?;
 		}
+		let redeem_d: T::AtLeast64BitUnsigned =
+			Self::pool_token_to_d(amount, d, pool_token_supply).ok_or(Error::<T>::Math)?;
 
+		let rates = Self::get_rates(&pool_info)?;
 		for i in 0..pool_info.balances.len() {
 			let balance_i: T::AtLeast64BitUnsigned = balances[i];
 			let diff_i: T::AtLeast64BitUnsigned = balance_i
-				.checked_mul(&amount)
+				.checked_mul(&redeem_d)
 				.ok_or(Error::<T>::Math)?
 				.checked_div(&d)
 				.ok_or(Error::<T>::Math)?;
 			balances[i] = balance_i.checked_sub(&diff_i).ok_or(Error::<T>::Math)?;
 			let amounts_i: T::AtLeast64BitUnsigned =
-				diff_i.checked_div(&pool_info.precisions[i]).ok_or(Error::<T>::Math)?;
+				Self::denormalize(diff_i, pool_info.precisions[i], rates[i]).ok_or(Error::<T>::Math)?;
 			amounts.push(amounts_i);
 		}
-		let total_supply: T::AtLeast64BitUnsigned = d.checked_sub(&amount).ok_or(Error::<T>::Math)?;
+		let total_supply: T::AtLeast64BitUnsigned = d.checked_sub(&redeem_d).ok_or(Error::<T>::Math)?;
+		// the share of the fee kept for the liquidity providers is burned along with the redeemed amount
+		let redeem_amount: T::AtLeast64BitUnsigned = amount.checked_add(&lp_fee_amount).ok_or(Error::<T>::Math)?;
 		Ok(RedeemProportionResult {
 			amounts: Self::convert_vec_number_to_balance(amounts),
 			balances: Self::convert_vec_number_to_balance(balances),
 			fee_amount: fee_amount.into(),
+			lp_fee_amount: lp_fee_amount.into(),
 			total_supply: total_supply.into(),
-			redeem_amount: amount.into(),
+			redeem_amount: redeem_amount.into(),
 		})
 	}
 
@@ -1138,10 +3029,11 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 		amount_bal: T::Balance,
 		i: PoolTokenIndex,
-	) -> Result<RedeemSingleResult<T>, Error<T>> {
+	) -> Result<RedeemSingleResult<T::Balance>, Error<T>> {
 		// update pool balances and total supply to avoid stale data
 		let pool_info = Self::get_balance_update_amount(pool_info)?;
 		let pool_info = Self::get_collect_yield_amount(&pool_info)?;
@@ -1157,7 +3049,7 @@ impl<T: Config> Pallet<T> {
 			return Err(Error::<T>::ArgumentsError);
 		}
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		let a: T::AtLeast64BitUnsigned = Self::get_a(
 			pool_info.a,
 			pool_info.a_block,
@@ -1166,8 +3058,10 @@ impl<T: Config> Pallet<T> {
 		)
 		.ok_or(Error::<T>::Math)?;
 		let d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
+		let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
 		let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
 		let mut fee_amount: T::AtLeast64BitUnsigned = zero;
+		let mut lp_fee_amount: T::AtLeast64BitUnsigned = zero;
 
 		if pool_info.redeem_fee > zero {
 			fee_amount = amount
@@ -1177,28 +3071,37 @@ impl<T: Config> Pallet<T> {
 				.ok_or(Error::<T>::Math)?;
 			// Redemption fee is charged with pool token before redemption.
 			amount = amount.checked_sub(&fee_amount).ok_or(Error::<T>::Math)?;
+			(fee_amount, lp_fee_amount) = Self::split_fee(fee_amount, pool_info.admin_fee).ok_or(Error::<T>::Math)?;
 		}
+		let redeem_d: T::AtLeast64BitUnsigned =
+			Self::pool_token_to_d(amount, d, pool_token_supply).ok_or(Error::<T>::Math)?;
 
-		// The pool token amount becomes D - _amount
-		let y: T::AtLeast64BitUnsigned =
-			Self::get_y(&balances, i, d.checked_sub(&amount).ok_or(Error::<T>::Math)?, a).ok_or(Error::<T>::Math)?;
-		// dy = (balance[i] - y - 1) / precisions[i] in case there was rounding errors
+		// The D becomes D - redeem_d
+		let total_supply: T::AtLeast64BitUnsigned = d.checked_sub(&redeem_d).ok_or(Error::<T>::Math)?;
+		let y: T::AtLeast64BitUnsigned = Self::get_y(&balances, i, total_supply, a).ok_or(Error::<T>::Math)?;
+		// dy = (balance[i] - y - 1) / precisions[i] / rates[i] in case there was rounding errors
 		let balance_i: T::AtLeast64BitUnsigned = pool_info.balances[i_usize].into();
-		let dy: T::AtLeast64BitUnsigned = balance_i
-			.checked_sub(&y)
-			.ok_or(Error::<T>::Math)?
-			.checked_sub(&one)
-			.ok_or(Error::<T>::Math)?
-			.checked_div(&pool_info.precisions[i_usize])
-			.ok_or(Error::<T>::Math)?;
-		let total_supply: T::AtLeast64BitUnsigned = d.checked_sub(&amount).ok_or(Error::<T>::Math)?;
+		let rate_i = Self::get_rates(&pool_info)?[i_usize];
+		let dy: T::AtLeast64BitUnsigned = Self::denormalize(
+			balance_i
+				.checked_sub(&y)
+				.ok_or(Error::<T>::Math)?
+				.checked_sub(&one)
+				.ok_or(Error::<T>::Math)?,
+			pool_info.precisions[i_usize],
+			rate_i,
+		)
+		.ok_or(Error::<T>::Math)?;
 		balances[i_usize] = y;
+		// the share of the fee kept for the liquidity providers is burned along with the redeemed amount
+		let redeem_amount: T::AtLeast64BitUnsigned = amount.checked_add(&lp_fee_amount).ok_or(Error::<T>::Math)?;
 		Ok(RedeemSingleResult {
 			dy: dy.into(),
 			fee_amount: fee_amount.into(),
+			lp_fee_amount: lp_fee_amount.into(),
 			total_supply: total_supply.into(),
 			balances: Self::convert_vec_number_to_balance(balances),
-			redeem_amount: amount.into(),
+			redeem_amount: redeem_amount.into(),
 		})
 	}
 
@@ -1209,9 +3112,10 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 		amounts: &[T::Balance],
-	) -> Result<RedeemMultiResult<T>, Error<T>> {
+	) -> Result<RedeemMultiResult<T::Balance>, Error<T>> {
 		// update pool balances and total supply to avoid stale data
 		let pool_info = Self::get_balance_update_amount(pool_info)?;
 		let pool_info = Self::get_collect_yield_amount(&pool_info)?;
@@ -1220,7 +3124,7 @@ impl<T: Config> Pallet<T> {
 			return Err(Error::<T>::ArgumentsError);
 		}
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		let a: T::AtLeast64BitUnsigned = Self::get_a(
 			pool_info.a,
 			pool_info.a_block,
@@ -1230,21 +3134,30 @@ impl<T: Config> Pallet<T> {
 		.ok_or(Error::<T>::Math)?;
 		let old_d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
 		let zero: T::AtLeast64BitUnsigned = Zero::zero();
+		let rates = Self::get_rates(&pool_info)?;
 		for i in 0..balances.len() {
 			let amounts_i: T::AtLeast64BitUnsigned = amounts[i].into();
 			if amounts_i == zero {
 				continue;
 			}
 			let balance_i: T::AtLeast64BitUnsigned = balances[i];
-			// balance = balance + amount * precision
-			let sub_amount: T::AtLeast64BitUnsigned = amounts_i
-				.checked_mul(&pool_info.precisions[i])
-				.ok_or(Error::<T>::Math)?;
+			// balance = balance - amount * precision * rate
+			let sub_amount: T::AtLeast64BitUnsigned =
+				Self::normalize(amounts_i, pool_info.precisions[i], rates[i]).ok_or(Error::<T>::Math)?;
 			balances[i] = balance_i.checked_sub(&sub_amount).ok_or(Error::<T>::Math)?;
 		}
 		let new_d: T::AtLeast64BitUnsigned = Self::get_d(&balances, a).ok_or(Error::<T>::Math)?;
-		let mut redeem_amount: T::AtLeast64BitUnsigned = old_d.checked_sub(&new_d).ok_or(Error::<T>::Math)?;
+		// the pool token amount worth the D decrease, rounded up in favour of the pool
+		let sub_amount: T::AtLeast64BitUnsigned = Self::d_to_pool_token(
+			old_d.checked_sub(&new_d).ok_or(Error::<T>::Math)?,
+			old_d,
+			pool_info.pool_token_supply.into(),
+			true,
+		)
+		.ok_or(Error::<T>::Math)?;
+		let mut redeem_amount: T::AtLeast64BitUnsigned = sub_amount;
 		let mut fee_amount: T::AtLeast64BitUnsigned = zero;
+		let mut lp_fee_amount: T::AtLeast64BitUnsigned = zero;
 		if pool_info.redeem_fee > zero {
 			let fee_denominator: T::AtLeast64BitUnsigned = T::FeePrecision::get();
 			let div_amount: T::AtLeast64BitUnsigned = fee_denominator
@@ -1255,16 +3168,16 @@ impl<T: Config> Pallet<T> {
 				.ok_or(Error::<T>::Math)?
 				.checked_div(&div_amount)
 				.ok_or(Error::<T>::Math)?;
-			let sub_amount: T::AtLeast64BitUnsigned = old_d.checked_sub(&new_d).ok_or(Error::<T>::Math)?;
 			fee_amount = redeem_amount.checked_sub(&sub_amount).ok_or(Error::<T>::Math)?;
+			(fee_amount, lp_fee_amount) = Self::split_fee(fee_amount, pool_info.admin_fee).ok_or(Error::<T>::Math)?;
 		}
 		let burn_amount: T::AtLeast64BitUnsigned = redeem_amount.checked_sub(&fee_amount).ok_or(Error::<T>::Math)?;
-		let total_supply: T::AtLeast64BitUnsigned = old_d.checked_sub(&burn_amount).ok_or(Error::<T>::Math)?;
 		Ok(RedeemMultiResult {
 			redeem_amount: redeem_amount.into(),
 			fee_amount: fee_amount.into(),
+			lp_fee_amount: lp_fee_amount.into(),
 			balances: Self::convert_vec_number_to_balance(balances),
-			total_supply: total_supply.into(),
+			total_supply: new_d.into(),
 			burn_amount: burn_amount.into(),
 		})
 	}
@@ -1276,10 +3189,11 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 	) -> Result<PendingFeeResult<T>, Error<T>> {
 		let mut balances: Vec<T::AtLeast64BitUnsigned> =
-			Self::convert_vec_balance_to_number(pool_info.balances.clone());
+			Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		let a: T::AtLeast64BitUnsigned = Self::get_a(
 			pool_info.a,
 			pool_info.a_block,
@@ -1288,30 +3202,53 @@ impl<T: Config> Pallet<T> {
 		)
 		.ok_or(Error::<T>::Math)?;
 		let old_d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
+		let rates = Self::get_rates(pool_info)?;
 		for (i, balance) in balances.iter_mut().enumerate() {
 			let balance_of: T::AtLeast64BitUnsigned =
 				T::Assets::balance(pool_info.assets[i], &pool_info.account_id).into();
-			*balance = balance_of
-				.checked_mul(&pool_info.precisions[i])
-				.ok_or(Error::<T>::Math)?;
+			*balance = Self::normalize(balance_of, pool_info.precisions[i], rates[i]).ok_or(Error::<T>::Math)?;
 		}
 		let new_d: T::AtLeast64BitUnsigned = Self::get_d(&balances, a).ok_or(Error::<T>::Math)?;
 		let old_d_div_100: T::AtLeast64BitUnsigned = old_d.checked_div(&100u128.into()).ok_or(Error::<T>::Math)?;
 		let old_d_margin: T::AtLeast64BitUnsigned = old_d.checked_sub(&old_d_div_100).ok_or(Error::<T>::Math)?;
 
 		if new_d > old_d {
-			let fee_amount: T::AtLeast64BitUnsigned = new_d.checked_sub(&old_d).ok_or(Error::<T>::Math)?;
+			let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
+			let (admin_fee_amount, lp_fee_amount) =
+				Self::split_fee(new_d.checked_sub(&old_d).ok_or(Error::<T>::Math)?, pool_info.admin_fee)
+					.ok_or(Error::<T>::Math)?;
+			// mint the pool tokens which are worth the share of the fee recipient once minted
+			let fee_amount: T::AtLeast64BitUnsigned = if pool_token_supply.is_zero() {
+				admin_fee_amount
+			} else {
+				Self::mul_div(
+					admin_fee_amount,
+					pool_token_supply,
+					new_d.checked_sub(&admin_fee_amount).ok_or(Error::<T>::Math)?,
+					false,
+				)
+				.ok_or(Error::<T>::Math)?
+			};
+			let lp_fee_amount: T::AtLeast64BitUnsigned =
+				Self::d_to_pool_token(lp_fee_amount, old_d, pool_token_supply, false).ok_or(Error::<T>::Math)?;
 			Ok(PendingFeeResult {
 				fee_amount: fee_amount.into(),
+				lp_fee_amount: lp_fee_amount.into(),
 				balances: Self::convert_vec_number_to_balance(balances),
 				total_supply: new_d.into(),
+				pool_token_supply: pool_token_supply
+					.checked_add(&fee_amount)
+					.ok_or(Error::<T>::Math)?
+					.into(),
 			})
 		} else if new_d >= old_d_margin {
 			// this is due to rounding issues for token balance conversion
 			Ok(PendingFeeResult {
 				fee_amount: Zero::zero(),
+				lp_fee_amount: Zero::zero(),
 				balances: Self::convert_vec_number_to_balance(balances),
 				total_supply: new_d.into(),
+				pool_token_supply: pool_info.pool_token_supply,
 			})
 		} else {
 			Err(Error::<T>::Math)
@@ -1325,9 +3262,17 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 	) -> Result<
-		StableAssetPoolInfo<T::AssetId, T::AtLeast64BitUnsigned, T::Balance, T::AccountId, BlockNumberFor<T>>,
+		StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
 		Error<T>,
 	> {
 		let a: T::AtLeast64BitUnsigned = Self::get_a(
@@ -1337,9 +3282,20 @@ impl<T: Config> Pallet<T> {
 			pool_info.future_a_block,
 		)
 		.ok_or(Error::<T>::Math)?;
-		let balances: Vec<T::AtLeast64BitUnsigned> = Self::convert_vec_balance_to_number(pool_info.balances.clone());
+		let balances: Vec<T::AtLeast64BitUnsigned> = Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
 		let new_d: T::AtLeast64BitUnsigned = Self::get_d(&balances, a).ok_or(Error::<T>::Math)?;
+		let old_d: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
 		let mut cloned_stable_asset_info = pool_info.clone();
+		if new_d > old_d {
+			// the yield is minted to the yield recipient at the pool token price before it
+			let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
+			let yield_amount: T::AtLeast64BitUnsigned =
+				Self::d_to_pool_token(new_d - old_d, old_d, pool_token_supply, false).ok_or(Error::<T>::Math)?;
+			cloned_stable_asset_info.pool_token_supply = pool_token_supply
+				.checked_add(&yield_amount)
+				.ok_or(Error::<T>::Math)?
+				.into();
+		}
 		cloned_stable_asset_info.total_supply = new_d.into();
 		Ok(cloned_stable_asset_info)
 	}
@@ -1351,24 +3307,553 @@ impl<T: Config> Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
 	) -> Result<
-		StableAssetPoolInfo<T::AssetId, T::AtLeast64BitUnsigned, T::Balance, T::AccountId, BlockNumberFor<T>>,
+		StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
 		Error<T>,
 	> {
+		let rates = Self::get_rates(pool_info)?;
 		let mut updated_balances = pool_info.balances.clone();
 		for (i, balance) in updated_balances.iter_mut().enumerate() {
 			let balance_of: T::AtLeast64BitUnsigned =
 				T::Assets::balance(pool_info.assets[i], &pool_info.account_id).into();
-			*balance = balance_of
-				.checked_mul(&pool_info.precisions[i])
+			*balance = Self::normalize(balance_of, pool_info.precisions[i], rates[i])
 				.ok_or(Error::<T>::Math)?
 				.into();
 		}
 		let mut cloned_stable_asset_info = pool_info.clone();
 		cloned_stable_asset_info.balances = updated_balances;
+		if let Some(total_supply) = Self::get_rate_change_d(pool_info, &rates)? {
+			// the D the recorded balances gain or lose from the rate changes accrues to the liquidity
+			// providers, so only the D gained beyond it is collected as yield
+			cloned_stable_asset_info.total_supply = total_supply.into();
+		}
 		Ok(cloned_stable_asset_info)
 	}
+
+	/// The D of the recorded balances of a pool at `rates`, if they were last normalised at other rates.
+	pub(crate) fn get_rate_change_d(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		rates: &[T::AtLeast64BitUnsigned],
+	) -> Result<Option<T::AtLeast64BitUnsigned>, Error<T>> {
+		let Some(recorded_rates) = PoolAssetPools::<T>::get(pool_info.pool_asset).and_then(PoolRates::<T>::get) else {
+			return Ok(None);
+		};
+		// the rates of pools whose assets changed since are not comparable
+		if recorded_rates.len() != rates.len() || recorded_rates[..] == rates[..] || pool_info.total_supply.is_zero() {
+			return Ok(None);
+		}
+		let balances = pool_info
+			.balances
+			.iter()
+			.zip(rates.iter().zip(recorded_rates.iter()))
+			.map(|(balance, (rate, recorded_rate))| {
+				let balance: T::AtLeast64BitUnsigned = (*balance).into();
+				balance.checked_mul(rate)?.checked_div(recorded_rate)
+			})
+			.collect::<Option<Vec<T::AtLeast64BitUnsigned>>>()
+			.ok_or(Error::<T>::Math)?;
+		let a: T::AtLeast64BitUnsigned = Self::get_a(
+			pool_info.a,
+			pool_info.a_block,
+			pool_info.future_a,
+			pool_info.future_a_block,
+		)
+		.ok_or(Error::<T>::Math)?;
+		Self::get_d(&balances, a).ok_or(Error::<T>::Math).map(Some)
+	}
+
+	/// Check the invariants of every pool:
+	///
+	/// * the pool id is below `PoolCount`;
+	/// * every recorded balance is backed by the pool account, and lags behind its holdings by at most 1%
+	///   (pending yield or fees, the margin `collect_fee` tolerates);
+	/// * `total_supply` is within 1% of the D of the recorded balances at the current A;
+	/// * the pool token issuance matches `pool_token_supply`, which is at most `total_supply` as the fees
+	///   kept for the liquidity providers only raise D.
+	///
+	/// The status and restrictions of a pool are only stored for existing pools.
+	#[cfg(any(feature = "try-runtime", test))]
+	pub(crate) fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+		let within_margin = |value: T::AtLeast64BitUnsigned, reference: T::AtLeast64BitUnsigned| -> bool {
+			let margin = reference.checked_div(&100u128.into()).unwrap_or_else(Zero::zero);
+			reference.checked_add(&margin).is_none_or(|upper| value <= upper)
+				&& value.checked_add(&margin).is_none_or(|value| value >= reference)
+		};
+
+		let pool_count = PoolCount::<T>::get();
+		for (pool_id, pool_info) in Pools::<T>::iter() {
+			ensure!(pool_id < pool_count, "pool id is not below the pool count");
+			ensure!(
+				pool_info.assets.len() == pool_info.precisions.len()
+					&& pool_info.assets.len() == pool_info.balances.len(),
+				"pool vectors have different lengths"
+			);
+			ensure!(
+				PoolAssetPools::<T>::get(pool_info.pool_asset) == Some(pool_id),
+				"pool asset is not indexed to its pool"
+			);
+
+			let base_index = Self::get_base_pool(&pool_info).map(|(index, _)| index as usize);
+			let mut balances: Vec<T::AtLeast64BitUnsigned> = Vec::new();
+			for (i, balance) in pool_info.balances.iter().enumerate() {
+				let recorded: T::AtLeast64BitUnsigned = (*balance).into();
+				balances.push(recorded);
+				// the balances of assets with a rate are valued at the rate of the last pool update, which
+				// may have moved since
+				if base_index == Some(i) || T::RateProvider::get_rate(pool_info.assets[i]).is_some() {
+					continue;
+				}
+				let held: T::AtLeast64BitUnsigned =
+					T::Assets::balance(pool_info.assets[i], &pool_info.account_id).into();
+				let held = held
+					.checked_mul(&pool_info.precisions[i])
+					.ok_or("pool balance overflow")?;
+				ensure!(recorded <= held, "pool balance is not backed by the pool account");
+				ensure!(
+					within_margin(recorded, held),
+					"pool balance is out of sync with the pool account"
+				);
+			}
+
+			let total_supply: T::AtLeast64BitUnsigned = pool_info.total_supply.into();
+			let a = Self::get_a(
+				pool_info.a,
+				pool_info.a_block,
+				pool_info.future_a,
+				pool_info.future_a_block,
+			)
+			.ok_or("cannot compute A")?;
+			let d = Self::get_d(&balances, a).ok_or("cannot compute D")?;
+			ensure!(within_margin(d, total_supply), "total supply does not match D");
+
+			ensure!(
+				T::Assets::total_issuance(pool_info.pool_asset) == pool_info.pool_token_supply,
+				"pool token issuance does not match pool token supply"
+			);
+		}
+
+		for pool_id in PoolAssetPools::<T>::iter_values() {
+			ensure!(Pools::<T>::contains_key(pool_id), "pool asset of a missing pool");
+		}
+		for (pool_id, base_pool_id) in BasePools::<T>::iter() {
+			ensure!(
+				MetaPools::<T>::contains_key(base_pool_id, pool_id),
+				"metapool is not indexed to its base pool"
+			);
+			let pool_info = Pools::<T>::get(pool_id).ok_or("base pool of a missing pool")?;
+			ensure!(
+				Self::get_base_pool(&pool_info).is_some(),
+				"metapool does not hold the pool token of its base pool"
+			);
+		}
+		for (base_pool_id, pool_id, ()) in MetaPools::<T>::iter() {
+			ensure!(
+				BasePools::<T>::get(pool_id) == Some(base_pool_id),
+				"metapool index does not match the base pool"
+			);
+		}
+		for pool_id in PoolStatuses::<T>::iter_keys().chain(Restrictions::<T>::iter_keys()) {
+			ensure!(
+				Pools::<T>::contains_key(pool_id),
+				"pool status or restrictions of a missing pool"
+			);
+		}
+		Ok(())
+	}
+
+	/// Ensure the fees of a pool are within the configured maximums. The swap fee is checked at its
+	/// maximum, the fee of a swap out of a fully imbalanced pool.
+	pub(crate) fn ensure_fees_valid(
+		mint_fee: T::AtLeast64BitUnsigned,
+		swap_fee: T::AtLeast64BitUnsigned,
+		redeem_fee: T::AtLeast64BitUnsigned,
+		offpeg_fee_multiplier: T::AtLeast64BitUnsigned,
+	) -> DispatchResult {
+		let fee_denominator = T::FeePrecision::get();
+		let max_swap_fee = if offpeg_fee_multiplier > fee_denominator {
+			swap_fee
+				.checked_mul(&offpeg_fee_multiplier)
+				.and_then(|x| x.checked_div(&fee_denominator))
+				.ok_or(Error::<T>::SwapFeeTooHigh)?
+		} else {
+			swap_fee
+		};
+		ensure!(mint_fee <= T::MaxMintFee::get(), Error::<T>::MintFeeTooHigh);
+		ensure!(max_swap_fee <= T::MaxSwapFee::get(), Error::<T>::SwapFeeTooHigh);
+		ensure!(redeem_fee <= T::MaxRedeemFee::get(), Error::<T>::RedeemFeeTooHigh);
+		Ok(())
+	}
+
+	/// Ensure neither the pallet nor the pool is paused.
+	pub(crate) fn ensure_pool_active(pool_id: StableAssetPoolId) -> DispatchResult {
+		ensure!(
+			!Paused::<T>::get() && PoolStatuses::<T>::get(pool_id) == PoolStatus::Active,
+			Error::<T>::PoolPaused
+		);
+		Ok(())
+	}
+
+	/// Add `iterations` to the Newton iterations of `get_d` and `get_y` taken in the current dispatch.
+	/// Outside of a dispatch they are not counted.
+	pub(crate) fn count_newton_iterations(iterations: u32) {
+		let _ = with_context::<NewtonIterations, _>(|count| {
+			let count = count.or_default();
+			count.solves = count.solves.saturating_add(1);
+			count.iterations = count.iterations.saturating_add(iterations);
+		});
+	}
+
+	/// `weight` plus the weight of the most Newton iterations `max_solves` calls of `get_d` and `get_y` on
+	/// pools of `asset_length` assets may take, charged upfront by the user extrinsics. `weight` is
+	/// benchmarked on balanced pools, where each call converges in the fewest iterations.
+	pub(crate) fn with_max_newton_iterations(weight: Weight, asset_length: u32, max_solves: u32) -> Weight {
+		let max_iterations = max_solves.saturating_mul(NUMBER_OF_ITERATIONS_TO_CONVERGE as u32);
+		weight.saturating_add(T::WeightInfo::newton_iteration(asset_length).saturating_mul(max_iterations.into()))
+	}
+
+	/// Dispatch the pool operations of a user extrinsic, charging `weight` plus the Newton iterations they
+	/// took on pools of `asset_length` assets, and refunding the rest of the weight charged upfront for
+	/// `max_solves` calls of `get_d` and `get_y`. Failed operations are charged in full.
+	pub(crate) fn dispatch_with_newton_iterations<R>(
+		weight: Weight,
+		asset_length: u32,
+		max_solves: u32,
+		operation: impl FnOnce() -> Result<R, DispatchError>,
+	) -> DispatchResultWithPostInfo {
+		let (result, count) = Self::with_newton_count(operation);
+		debug_assert!(
+			count.solves <= max_solves,
+			"{} calls of get_d and get_y exceed the {} charged upfront",
+			count.solves,
+			max_solves
+		);
+		result?;
+		Ok(Some(
+			weight
+				.saturating_add(T::WeightInfo::newton_iteration(asset_length).saturating_mul(count.iterations.into())),
+		)
+		.into())
+	}
+
+	/// Run `operation`, returning its result along with the Newton iterations of `get_d` and `get_y` it
+	/// took.
+	#[cfg(any(feature = "runtime-benchmarks", test))]
+	pub(crate) fn with_newton_iterations<R>(operation: impl FnOnce() -> R) -> (R, u32) {
+		let (result, count) = Self::with_newton_count(operation);
+		(result, count.iterations)
+	}
+
+	/// Run `operation`, returning its result along with the calls of `get_d` and `get_y` it made and the
+	/// Newton iterations they took.
+	fn with_newton_count<R>(operation: impl FnOnce() -> R) -> (R, NewtonIterations) {
+		run_in_context(|| {
+			let count = || with_context::<NewtonIterations, _>(|count| *count.or_default()).unwrap_or_default();
+			let start = count();
+			let result = operation();
+			let end = count();
+			(
+				result,
+				NewtonIterations {
+					solves: end.solves.saturating_sub(start.solves),
+					iterations: end.iterations.saturating_sub(start.iterations),
+				},
+			)
+		})
+	}
+
+	/// The number of assets of a pool, which the weights of its operations scale with.
+	pub(crate) fn get_asset_length(pool_id: StableAssetPoolId) -> Result<u32, Error<T>> {
+		Pools::<T>::get(pool_id)
+			.map(|pool_info| pool_info.assets.len() as u32)
+			.ok_or(Error::<T>::PoolNotFound)
+	}
+
+	/// Ensure the current block is not after the `deadline` given to a user extrinsic, if any.
+	pub(crate) fn ensure_deadline(deadline: Option<BlockNumberFor<T>>) -> DispatchResult {
+		if let Some(deadline) = deadline {
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= deadline,
+				Error::<T>::DeadlinePassed
+			);
+		}
+		Ok(())
+	}
+
+	/// Ensure neither the pallet nor the pool is paused, allowing pools being destroyed.
+	pub(crate) fn ensure_pool_redeemable(pool_id: StableAssetPoolId) -> DispatchResult {
+		ensure!(
+			!Paused::<T>::get() && PoolStatuses::<T>::get(pool_id) != PoolStatus::Paused,
+			Error::<T>::PoolPaused
+		);
+		Ok(())
+	}
+
+	/// Ensure the operation is enabled on the pool and none of the given assets is disabled.
+	pub(crate) fn ensure_operation_allowed(
+		pool_id: StableAssetPoolId,
+		operation: PoolOperation,
+		input_indexes: &[PoolTokenIndex],
+		output_indexes: &[PoolTokenIndex],
+	) -> DispatchResult {
+		let restrictions = Restrictions::<T>::get(pool_id);
+		ensure!(
+			!restrictions.is_operation_disabled(operation),
+			Error::<T>::OperationDisabled
+		);
+		ensure!(
+			!input_indexes.iter().any(|i| restrictions.is_input_disabled(*i))
+				&& !output_indexes.iter().any(|i| restrictions.is_output_disabled(*i)),
+			Error::<T>::AssetDisabled
+		);
+		Ok(())
+	}
+
+	/// Ensure a route step can be executed.
+	pub(crate) fn ensure_route_step_allowed(step: &SwapRouteStep) -> DispatchResult {
+		let pool_id = step.pool_id();
+		match *step {
+			SwapRouteStep::Swap {
+				input_index,
+				output_index,
+				..
+			} => {
+				Self::ensure_pool_active(pool_id)?;
+				Self::ensure_operation_allowed(pool_id, PoolOperation::Swap, &[input_index], &[output_index])
+			}
+			SwapRouteStep::Mint { input_index, .. } => {
+				Self::ensure_pool_active(pool_id)?;
+				Self::ensure_operation_allowed(pool_id, PoolOperation::Mint, &[input_index], &[])
+			}
+			SwapRouteStep::RedeemSingle { output_index, .. } => {
+				Self::ensure_pool_redeemable(pool_id)?;
+				Self::ensure_operation_allowed(pool_id, PoolOperation::RedeemSingle, &[], &[output_index])
+			}
+		}
+	}
+
+	/// Return the indexes of the non-zero amounts.
+	fn non_zero_indexes(amounts: &[T::Balance]) -> Vec<PoolTokenIndex> {
+		amounts
+			.iter()
+			.enumerate()
+			.filter(|(_, amount)| !amount.is_zero())
+			.map(|(i, _)| i as PoolTokenIndex)
+			.collect()
+	}
+
+	pub(crate) fn swap_route_weight(route: &[SwapRouteStep]) -> Weight {
+		let asset_length = T::PoolAssetLimit::get();
+		route
+			.iter()
+			.take(T::MaxRouteLength::get() as usize)
+			.fold(Weight::zero(), |weight, step| {
+				weight.saturating_add(match step {
+					SwapRouteStep::Swap { .. } => T::WeightInfo::swap(asset_length),
+					SwapRouteStep::Mint { .. } => T::WeightInfo::mint(asset_length),
+					SwapRouteStep::RedeemSingle { .. } => T::WeightInfo::redeem_single(asset_length),
+				})
+			})
+	}
+
+	/// The weight of `swap_underlying`, a swap in the metapool along with a mint into or a redeem from
+	/// the base pool.
+	pub(crate) fn swap_underlying_weight() -> Weight {
+		let asset_length = T::PoolAssetLimit::get();
+		T::WeightInfo::swap(asset_length)
+			.saturating_add(T::WeightInfo::mint(asset_length).max(T::WeightInfo::redeem_single(asset_length)))
+	}
+
+	/// Return the input and output asset of a route step.
+	pub(crate) fn get_route_step_assets(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		step: &SwapRouteStep,
+	) -> Result<(T::AssetId, T::AssetId), Error<T>> {
+		let asset_at = |index: PoolTokenIndex| -> Result<T::AssetId, Error<T>> {
+			pool_info
+				.assets
+				.get(index as usize)
+				.copied()
+				.ok_or(Error::<T>::InvalidRoute)
+		};
+		match *step {
+			SwapRouteStep::Swap {
+				input_index,
+				output_index,
+				..
+			} => {
+				ensure!(input_index != output_index, Error::<T>::InvalidRoute);
+				Ok((asset_at(input_index)?, asset_at(output_index)?))
+			}
+			SwapRouteStep::Mint { input_index, .. } => Ok((asset_at(input_index)?, pool_info.pool_asset)),
+			SwapRouteStep::RedeemSingle { output_index, .. } => Ok((pool_info.pool_asset, asset_at(output_index)?)),
+		}
+	}
+
+	/// Return the output amount of a route step, making sure the pool can afford it.
+	pub(crate) fn get_route_step_output_amount(
+		pool_info: &StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+		step: &SwapRouteStep,
+		amount: T::Balance,
+	) -> Result<T::Balance, Error<T>> {
+		let (_, output_asset) = Self::get_route_step_assets(pool_info, step)?;
+		let output_amount = match *step {
+			SwapRouteStep::Swap {
+				input_index,
+				output_index,
+				..
+			} => Self::get_swap_amount(pool_info, input_index, output_index, amount)?.dy,
+			SwapRouteStep::Mint { input_index, .. } => {
+				let mut amounts: Vec<T::Balance> = vec![Zero::zero(); pool_info.assets.len()];
+				amounts[input_index as usize] = amount;
+				return Ok(Self::get_mint_amount(pool_info, &amounts)?.mint_amount);
+			}
+			SwapRouteStep::RedeemSingle { output_index, .. } => {
+				Self::get_redeem_single_amount(pool_info, amount, output_index)?.dy
+			}
+		};
+		// make sure pool can afford the output amount
+		ensure!(
+			output_amount <= T::Assets::balance(output_asset, &pool_info.account_id),
+			Error::<T>::InvalidRoute
+		);
+		Ok(output_amount)
+	}
+
+	/// Depth first search for the best route from `input_asset` to `output_asset`, using every pool at
+	/// most once and never revisiting an asset. `pools_by_asset` indexes `pools` by their assets and pool
+	/// assets, so each hop only visits the pools holding its input asset.
+	fn search_route(
+		pools_by_asset: &BTreeMap<T::AssetId, Vec<usize>>,
+		pools: &[(
+			StableAssetPoolId,
+			StableAssetPoolInfo<
+				T::AssetId,
+				T::AtLeast64BitUnsigned,
+				T::Balance,
+				T::AccountId,
+				BlockNumberFor<T>,
+				PoolAssetLimitOf<T>,
+			>,
+		)],
+		input_asset: T::AssetId,
+		output_asset: T::AssetId,
+		amount: T::Balance,
+		hops_left: u32,
+		route: &mut Vec<SwapRouteStep>,
+		visited_assets: &mut Vec<T::AssetId>,
+		best: &mut Option<(Vec<SwapRouteStep>, T::Balance)>,
+	) {
+		if hops_left == 0 {
+			return;
+		}
+		let Some(pool_indices) = pools_by_asset.get(&input_asset) else {
+			return;
+		};
+		for (pool_id, pool_info) in pool_indices.iter().filter_map(|index| pools.get(*index)) {
+			if route.iter().any(|step| step.pool_id() == *pool_id) {
+				continue;
+			}
+			let mut steps: Vec<SwapRouteStep> = Vec::new();
+			if let Some(input_index) = pool_info.assets.iter().position(|a| *a == input_asset) {
+				let input_index = input_index as PoolTokenIndex;
+				for output_index in 0..pool_info.assets.len() as PoolTokenIndex {
+					if output_index != input_index {
+						steps.push(SwapRouteStep::Swap {
+							pool_id: *pool_id,
+							input_index,
+							output_index,
+						});
+					}
+				}
+				steps.push(SwapRouteStep::Mint {
+					pool_id: *pool_id,
+					input_index,
+				});
+			}
+			if pool_info.pool_asset == input_asset {
+				for output_index in 0..pool_info.assets.len() as PoolTokenIndex {
+					steps.push(SwapRouteStep::RedeemSingle {
+						pool_id: *pool_id,
+						output_index,
+					});
+				}
+			}
+
+			for step in steps {
+				if Self::ensure_route_step_allowed(&step).is_err() {
+					continue;
+				}
+				let Ok((_, next_asset)) = Self::get_route_step_assets(pool_info, &step) else {
+					continue;
+				};
+				if visited_assets.contains(&next_asset) {
+					continue;
+				}
+				let output_amount = match Self::get_route_step_output_amount(pool_info, &step, amount) {
+					Ok(output_amount) if output_amount > Zero::zero() => output_amount,
+					_ => continue,
+				};
+				route.push(step);
+				if next_asset == output_asset {
+					let is_better = match best {
+						Some((best_route, best_amount)) => {
+							output_amount > *best_amount
+								|| (output_amount == *best_amount && route.len() < best_route.len())
+						}
+						None => true,
+					};
+					if is_better {
+						*best = Some((route.clone(), output_amount));
+					}
+				} else {
+					visited_assets.push(next_asset);
+					Self::search_route(
+						pools_by_asset,
+						pools,
+						next_asset,
+						output_asset,
+						output_amount,
+						hops_left - 1,
+						route,
+						visited_assets,
+						best,
+					);
+					visited_assets.pop();
+				}
+				route.pop();
+			}
+		}
+	}
 }
 
 impl<T: Config> StableAsset for Pallet<T> {
@@ -1377,6 +3862,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 	type Balance = T::Balance;
 	type AccountId = T::AccountId;
 	type BlockNumber = BlockNumberFor<T>;
+	type PoolAssetLimit = PoolAssetLimitOf<T>;
 
 	fn pool_count() -> StableAssetPoolId {
 		PoolCount::<T>::get()
@@ -1391,6 +3877,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	> {
 		Pools::<T>::get(id)
@@ -1410,15 +3897,23 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	) -> DispatchResult {
-		let old_balances = pool_info.balances.clone();
+		let old_balances = pool_info.balances.to_vec();
 		let new_balances_pool_info = Self::get_balance_update_amount(pool_info)?;
 		pool_info.balances = new_balances_pool_info.balances;
+		pool_info.total_supply = new_balances_pool_info.total_supply;
+		let rates: BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>> = Self::get_rates(pool_info)?
+			.try_into()
+			.map_err(|_| Error::<T>::InconsistentStorage)?;
+		if PoolRates::<T>::get(pool_id).as_ref() != Some(&rates) {
+			PoolRates::<T>::insert(pool_id, rates);
+		}
 		Self::deposit_event(Event::BalanceUpdated {
 			pool_id,
 			old_balances,
-			new_balances: pool_info.balances.clone(),
+			new_balances: pool_info.balances.to_vec(),
 		});
 		Ok(())
 	}
@@ -1437,27 +3932,47 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	) -> DispatchResult {
 		let old_total_supply = pool_info.total_supply;
 		let old_d: T::AtLeast64BitUnsigned = old_total_supply.into();
+		// the D gained or lost from rate changes is kept in the total supply
 		Self::update_balance(pool_id, pool_info)?;
 
 		let updated_total_supply_pool_info = Self::get_collect_yield_amount(pool_info)?;
 		let new_d: T::AtLeast64BitUnsigned = updated_total_supply_pool_info.total_supply.into();
-
-		ensure!(new_d >= old_d, Error::<T>::InvalidPoolValue);
-		if new_d > old_d {
-			let a: T::AtLeast64BitUnsigned = Self::get_a(
-				pool_info.a,
-				pool_info.a_block,
-				pool_info.future_a,
-				pool_info.future_a_block,
-			)
-			.ok_or(Error::<T>::Math)?;
-			let yield_amount: T::AtLeast64BitUnsigned = new_d - old_d;
+		let old_pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
+		let new_pool_token_supply: T::AtLeast64BitUnsigned = updated_total_supply_pool_info.pool_token_supply.into();
+		let yield_amount: T::AtLeast64BitUnsigned = new_pool_token_supply - old_pool_token_supply;
+		pool_info.total_supply = new_d.into();
+		pool_info.pool_token_supply = new_pool_token_supply.into();
+		if new_d == old_d && yield_amount.is_zero() {
+			return Ok(());
+		}
+		let a: T::AtLeast64BitUnsigned = Self::get_a(
+			pool_info.a,
+			pool_info.a_block,
+			pool_info.future_a,
+			pool_info.future_a_block,
+		)
+		.ok_or(Error::<T>::Math)?;
+		if !yield_amount.is_zero() {
 			T::Assets::mint_into(pool_info.pool_asset, &pool_info.yield_recipient, yield_amount.into())?;
-			pool_info.total_supply = new_d.into();
+		}
+		let virtual_price = Self::get_pool_virtual_price(pool_info, pool_info.precision).ok_or(Error::<T>::Math)?;
+		if new_d < old_d {
+			// a lower D, such as after a rate drop, is a loss shared by the liquidity providers: the pool
+			// token supply is kept, so each pool token is worth less
+			Self::deposit_event(Event::LossShared {
+				pool_id,
+				a,
+				old_total_supply,
+				new_total_supply: pool_info.total_supply,
+				virtual_price,
+			});
+		}
+		if !yield_amount.is_zero() {
 			Self::deposit_event(Event::YieldCollected {
 				pool_id,
 				a,
@@ -1465,6 +3980,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 				new_total_supply: pool_info.total_supply,
 				who: pool_info.yield_recipient.clone(),
 				amount: yield_amount.into(),
+				virtual_price,
 			});
 		}
 		Ok(())
@@ -1484,21 +4000,27 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	) -> DispatchResult {
-		let old_balances = pool_info.balances.clone();
+		let old_balances = pool_info.balances.to_vec();
 		let old_total_supply = pool_info.total_supply;
 		let PendingFeeResult {
 			fee_amount,
+			lp_fee_amount,
 			balances,
 			total_supply,
+			pool_token_supply,
 		} = Self::get_pending_fee_amount(pool_info)?;
 		let zero: T::Balance = Zero::zero();
 		pool_info.total_supply = total_supply;
-		pool_info.balances = balances;
-		if fee_amount > zero {
+		pool_info.pool_token_supply = pool_token_supply;
+		pool_info.balances = balances.try_into().map_err(|_| Error::<T>::InconsistentStorage)?;
+		if fee_amount > zero || lp_fee_amount > zero {
 			let fee_recipient = pool_info.fee_recipient.clone();
-			T::Assets::mint_into(pool_info.pool_asset, &fee_recipient, fee_amount)?;
+			if fee_amount > zero {
+				T::Assets::mint_into(pool_info.pool_asset, &fee_recipient, fee_amount)?;
+			}
 			let a: T::AtLeast64BitUnsigned = Self::get_a(
 				pool_info.a,
 				pool_info.a_block,
@@ -1506,17 +4028,21 @@ impl<T: Config> StableAsset for Pallet<T> {
 				pool_info.future_a_block,
 			)
 			.ok_or(Error::<T>::Math)?;
+			let virtual_price = Self::get_pool_virtual_price(pool_info, pool_info.precision).ok_or(Error::<T>::Math)?;
 			Self::deposit_event(Event::FeeCollected {
 				pool_id,
 				a,
 				old_balances,
-				new_balances: pool_info.balances.clone(),
+				new_balances: pool_info.balances.to_vec(),
 				old_total_supply,
 				new_total_supply: total_supply,
 				who: fee_recipient,
 				amount: fee_amount,
+				lp_fee_amount,
+				virtual_price,
 			});
 		}
+		Self::update_oracle(pool_id, pool_info);
 		Ok(())
 	}
 
@@ -1547,16 +4073,26 @@ impl<T: Config> StableAsset for Pallet<T> {
 		precision: Self::AtLeast64BitUnsigned,
 	) -> DispatchResult {
 		ensure!(assets.len() > 1, Error::<T>::ArgumentsError);
-		let pool_asset_limit = T::PoolAssetLimit::get() as usize;
-		ensure!(assets.len() <= pool_asset_limit, Error::<T>::ArgumentsError);
+		// the pool tokens of a destroyed pool may still be outstanding, and must not be redeemable
+		// against a new pool
+		ensure!(
+			!PoolAssetPools::<T>::contains_key(pool_asset) && T::Assets::total_issuance(pool_asset).is_zero(),
+			Error::<T>::PoolAssetInUse
+		);
+		let assets: BoundedVec<T::AssetId, PoolAssetLimitOf<T>> =
+			assets.try_into().map_err(|_| Error::<T>::ArgumentsError)?;
 		ensure!(assets.len() == precisions.len(), Error::<T>::ArgumentsMismatch);
+		let precisions: BoundedVec<T::AtLeast64BitUnsigned, PoolAssetLimitOf<T>> =
+			precisions.try_into().map_err(|_| Error::<T>::ArgumentsMismatch)?;
+		Self::ensure_fees_valid(mint_fee, swap_fee, redeem_fee, Zero::zero())?;
+		ensure!(initial_a <= T::MaxA::get(), Error::<T>::AOverMax);
 		PoolCount::<T>::try_mutate(|pool_count| -> DispatchResult {
 			let pool_id = *pool_count;
 			let swap_id: T::AccountId = T::PalletId::get().into_sub_account_truncating(pool_id);
 			Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
 				ensure!(maybe_pool_info.is_none(), Error::<T>::InconsistentStorage);
 
-				let balances = sp_std::vec![Zero::zero(); assets.len()];
+				let balances = BoundedVec::truncate_from(sp_std::vec![Zero::zero(); assets.len()]);
 				frame_system::Pallet::<T>::inc_providers(&swap_id);
 				let current_block = frame_system::Pallet::<T>::block_number();
 				*maybe_pool_info = Some(StableAssetPoolInfo {
@@ -1566,7 +4102,10 @@ impl<T: Config> StableAsset for Pallet<T> {
 					mint_fee,
 					swap_fee,
 					redeem_fee,
+					offpeg_fee_multiplier: Zero::zero(),
+					admin_fee: T::FeePrecision::get(),
 					total_supply: Zero::zero(),
+					pool_token_supply: Zero::zero(),
 					a: initial_a,
 					a_block: current_block,
 					future_a: initial_a,
@@ -1582,6 +4121,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 			})?;
 
 			*pool_count = pool_id.checked_add(1).ok_or(Error::<T>::InconsistentStorage)?;
+			PoolAssetPools::<T>::insert(pool_asset, pool_id);
 
 			Self::deposit_event(Event::CreatePool {
 				pool_id,
@@ -1606,12 +4146,15 @@ impl<T: Config> StableAsset for Pallet<T> {
 		amounts: Vec<Self::Balance>,
 		min_mint_amount: Self::Balance,
 	) -> DispatchResult {
+		Self::ensure_pool_active(pool_id)?;
+		Self::ensure_operation_allowed(pool_id, PoolOperation::Mint, &Self::non_zero_indexes(&amounts), &[])?;
 		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
 			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
 			Self::collect_yield(pool_id, pool_info)?;
 			let MintResult {
 				mint_amount,
 				fee_amount,
+				lp_fee_amount,
 				balances,
 				total_supply,
 			} = Self::get_mint_amount(pool_info, &amounts)?;
@@ -1640,8 +4183,14 @@ impl<T: Config> StableAsset for Pallet<T> {
 				T::Assets::mint_into(pool_info.pool_asset, &pool_info.fee_recipient, fee_amount)?;
 			}
 			T::Assets::mint_into(pool_info.pool_asset, who, mint_amount)?;
+			let pool_token_supply: T::AtLeast64BitUnsigned = pool_info.pool_token_supply.into();
+			pool_info.pool_token_supply = pool_token_supply
+				.checked_add(&mint_amount.into())
+				.and_then(|x| x.checked_add(&fee_amount.into()))
+				.ok_or(Error::<T>::Math)?
+				.into();
 			pool_info.total_supply = total_supply;
-			pool_info.balances = balances;
+			pool_info.balances = balances.try_into().map_err(|_| Error::<T>::InconsistentStorage)?;
 			Self::collect_fee(pool_id, pool_info)?;
 			Self::deposit_event(Event::Minted {
 				minter: who.clone(),
@@ -1649,53 +4198,148 @@ impl<T: Config> StableAsset for Pallet<T> {
 				a,
 				input_amounts: amounts,
 				min_output_amount: min_mint_amount,
-				balances: pool_info.balances.clone(),
+				balances: pool_info.balances.to_vec(),
 				total_supply: pool_info.total_supply,
 				fee_amount,
+				lp_fee_amount,
 				output_amount: mint_amount,
 			});
 			Ok(())
 		})
 	}
 
-	/// Swap tokens
+	/// Swap tokens
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	/// * `i` - the array index of the input token in StableAssetPoolInfo.assets
+	/// * `j` - the array index of the output token in StableAssetPoolInfo.assets
+	/// * `dx` - the amount of input token
+	/// * `min_dy` - the minimum amount of output token received
+	/// * `asset_length` - the length of array in StableAssetPoolInfo.assets
+	fn swap(
+		who: &Self::AccountId,
+		pool_id: StableAssetPoolId,
+		i: PoolTokenIndex,
+		j: PoolTokenIndex,
+		dx: Self::Balance,
+		min_dy: Self::Balance,
+		asset_length: u32,
+	) -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
+		Self::ensure_pool_active(pool_id)?;
+		Self::ensure_operation_allowed(pool_id, PoolOperation::Swap, &[i], &[j])?;
+		Pools::<T>::try_mutate_exists(
+			pool_id,
+			|maybe_pool_info| -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
+				let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+				let asset_length_usize = asset_length as usize;
+				ensure!(asset_length_usize == pool_info.assets.len(), Error::<T>::ArgumentsError);
+				Self::collect_yield(pool_id, pool_info)?;
+				let SwapResult {
+					dx: _,
+					dy,
+					y,
+					balance_i,
+					fee_amount,
+					lp_fee_amount,
+				} = Self::get_swap_amount(pool_info, i, j, dx)?;
+				ensure!(dy >= min_dy, Error::<T>::SwapUnderMin);
+				let mut balances = pool_info.balances.to_vec();
+				let i_usize = i as usize;
+				let j_usize = j as usize;
+				balances[i_usize] = balance_i;
+				balances[j_usize] = y;
+				T::Assets::transfer(
+					pool_info.assets[i_usize],
+					who,
+					&pool_info.account_id,
+					dx,
+					Preservation::Expendable,
+				)?;
+				T::Assets::transfer(
+					pool_info.assets[j_usize],
+					&pool_info.account_id,
+					who,
+					dy,
+					Preservation::Expendable,
+				)?;
+				let asset_i = pool_info.assets[i_usize];
+				let asset_j = pool_info.assets[j_usize];
+
+				// Since the actual output amount is round down, collect fee should update the pool balances and
+				// total supply
+				Self::collect_fee(pool_id, pool_info)?;
+				let a: T::AtLeast64BitUnsigned = Self::get_a(
+					pool_info.a,
+					pool_info.a_block,
+					pool_info.future_a,
+					pool_info.future_a_block,
+				)
+				.ok_or(Error::<T>::Math)?;
+				Self::deposit_event(Event::TokenSwapped {
+					swapper: who.clone(),
+					pool_id,
+					a,
+					input_asset: asset_i,
+					output_asset: asset_j,
+					input_amount: dx,
+					min_output_amount: min_dy,
+					balances: pool_info.balances.to_vec(),
+					total_supply: pool_info.total_supply,
+					fee_amount,
+					lp_fee_amount,
+					output_amount: dy,
+				});
+				Ok((dx, dy))
+			},
+		)
+	}
+
+	/// Swap tokens for an exact amount of output token
 	///
 	/// # Arguments
 	///
 	/// * `pool_id` - the ID of the pool
 	/// * `i` - the array index of the input token in StableAssetPoolInfo.assets
 	/// * `j` - the array index of the output token in StableAssetPoolInfo.assets
-	/// * `dx` - the amount of input token
-	/// * `min_dy` - the minimum amount of output token received
+	/// * `dy` - the amount of output token received
+	/// * `max_dx` - the maximum amount of input token
 	/// * `asset_length` - the length of array in StableAssetPoolInfo.assets
-	fn swap(
+	fn swap_exact_output(
 		who: &Self::AccountId,
 		pool_id: StableAssetPoolId,
 		i: PoolTokenIndex,
 		j: PoolTokenIndex,
-		dx: Self::Balance,
-		min_dy: Self::Balance,
+		dy: Self::Balance,
+		max_dx: Self::Balance,
 		asset_length: u32,
 	) -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
+		Self::ensure_pool_active(pool_id)?;
+		Self::ensure_operation_allowed(pool_id, PoolOperation::Swap, &[i], &[j])?;
 		Pools::<T>::try_mutate_exists(
 			pool_id,
 			|maybe_pool_info| -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
 				let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
 				let asset_length_usize = asset_length as usize;
+				let i_usize = i as usize;
+				let j_usize = j as usize;
 				ensure!(asset_length_usize == pool_info.assets.len(), Error::<T>::ArgumentsError);
+				ensure!(
+					i != j && i_usize < asset_length_usize && j_usize < asset_length_usize,
+					Error::<T>::ArgumentsError
+				);
 				Self::collect_yield(pool_id, pool_info)?;
+				// the quoted input amount includes the SwapExactOverAmount buffer
+				let SwapResult { dx, .. } = Self::get_swap_amount_exact(pool_info, i, j, dy).ok_or(Error::<T>::Math)?;
+				ensure!(dx <= max_dx, Error::<T>::SwapOverMax);
 				let SwapResult {
-					dx: _,
-					dy,
-					y,
-					balance_i,
+					dy: actual_dy,
+					fee_amount,
+					lp_fee_amount,
+					..
 				} = Self::get_swap_amount(pool_info, i, j, dx)?;
-				ensure!(dy >= min_dy, Error::<T>::SwapUnderMin);
-				let mut balances = pool_info.balances.clone();
-				let i_usize = i as usize;
-				let j_usize = j as usize;
-				balances[i_usize] = balance_i;
-				balances[j_usize] = y;
+				ensure!(actual_dy >= dy, Error::<T>::ExactOutputNotReached);
 				T::Assets::transfer(
 					pool_info.assets[i_usize],
 					who,
@@ -1713,8 +4357,8 @@ impl<T: Config> StableAsset for Pallet<T> {
 				let asset_i = pool_info.assets[i_usize];
 				let asset_j = pool_info.assets[j_usize];
 
-				// Since the actual output amount is round down, collect fee should update the pool balances and
-				// total supply
+				// Only dy is transferred out, the rest of the output amount stays in the pool and collect fee
+				// updates the pool balances and total supply with it
 				Self::collect_fee(pool_id, pool_info)?;
 				let a: T::AtLeast64BitUnsigned = Self::get_a(
 					pool_info.a,
@@ -1730,9 +4374,11 @@ impl<T: Config> StableAsset for Pallet<T> {
 					input_asset: asset_i,
 					output_asset: asset_j,
 					input_amount: dx,
-					min_output_amount: min_dy,
-					balances: pool_info.balances.clone(),
+					min_output_amount: dy,
+					balances: pool_info.balances.to_vec(),
 					total_supply: pool_info.total_supply,
+					fee_amount,
+					lp_fee_amount,
 					output_amount: dy,
 				});
 				Ok((dx, dy))
@@ -1753,6 +4399,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 		amount: Self::Balance,
 		min_redeem_amounts: Vec<Self::Balance>,
 	) -> DispatchResult {
+		Self::ensure_operation_allowed(pool_id, PoolOperation::RedeemProportion, &[], &[])?;
 		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
 			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
 			Self::collect_yield(pool_id, pool_info)?;
@@ -1764,6 +4411,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 				amounts,
 				balances,
 				fee_amount,
+				lp_fee_amount,
 				total_supply,
 				redeem_amount,
 			} = Self::get_redeem_proportion_amount(pool_info, amount)?;
@@ -1797,7 +4445,8 @@ impl<T: Config> StableAsset for Pallet<T> {
 			)?;
 
 			pool_info.total_supply = total_supply;
-			pool_info.balances = balances;
+			pool_info.pool_token_supply = Self::sub_pool_token_supply(pool_info.pool_token_supply, redeem_amount)?;
+			pool_info.balances = balances.try_into().map_err(|_| Error::<T>::InconsistentStorage)?;
 			// Since the output amounts are round down, collect fee updates pool balances and total supply.
 			Self::collect_fee(pool_id, pool_info)?;
 			let a: T::AtLeast64BitUnsigned = Self::get_a(
@@ -1813,9 +4462,10 @@ impl<T: Config> StableAsset for Pallet<T> {
 				a,
 				input_amount: amount,
 				min_output_amounts: min_redeem_amounts,
-				balances: pool_info.balances.clone(),
+				balances: pool_info.balances.to_vec(),
 				total_supply: pool_info.total_supply,
 				fee_amount,
+				lp_fee_amount,
 				output_amounts: amounts,
 			});
 			Ok(())
@@ -1839,6 +4489,8 @@ impl<T: Config> StableAsset for Pallet<T> {
 		min_redeem_amount: Self::Balance,
 		asset_length: u32,
 	) -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
+		Self::ensure_pool_redeemable(pool_id)?;
+		Self::ensure_operation_allowed(pool_id, PoolOperation::RedeemSingle, &[], &[i])?;
 		Pools::<T>::try_mutate_exists(
 			pool_id,
 			|maybe_pool_info| -> sp_std::result::Result<(Self::Balance, Self::Balance), DispatchError> {
@@ -1847,6 +4499,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 				let RedeemSingleResult {
 					dy,
 					fee_amount,
+					lp_fee_amount,
 					total_supply,
 					balances,
 					redeem_amount,
@@ -1890,7 +4543,8 @@ impl<T: Config> StableAsset for Pallet<T> {
 				}
 
 				pool_info.total_supply = total_supply;
-				pool_info.balances = balances;
+				pool_info.pool_token_supply = Self::sub_pool_token_supply(pool_info.pool_token_supply, redeem_amount)?;
+				pool_info.balances = balances.try_into().map_err(|_| Error::<T>::InconsistentStorage)?;
 				// Since the output amounts are round down, collect fee updates pool balances and total supply.
 				Self::collect_fee(pool_id, pool_info)?;
 				let a: T::AtLeast64BitUnsigned = Self::get_a(
@@ -1907,9 +4561,10 @@ impl<T: Config> StableAsset for Pallet<T> {
 					input_amount: amount,
 					output_asset: pool_info.assets[i as usize],
 					min_output_amount: min_redeem_amount,
-					balances: pool_info.balances.clone(),
+					balances: pool_info.balances.to_vec(),
 					total_supply: pool_info.total_supply,
 					fee_amount,
+					lp_fee_amount,
 					output_amount: dy,
 				});
 				Ok((amount, dy))
@@ -1930,12 +4585,20 @@ impl<T: Config> StableAsset for Pallet<T> {
 		amounts: Vec<Self::Balance>,
 		max_redeem_amount: Self::Balance,
 	) -> DispatchResult {
+		Self::ensure_pool_redeemable(pool_id)?;
+		Self::ensure_operation_allowed(
+			pool_id,
+			PoolOperation::RedeemMulti,
+			&[],
+			&Self::non_zero_indexes(&amounts),
+		)?;
 		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
 			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
 			Self::collect_yield(pool_id, pool_info)?;
 			let RedeemMultiResult {
 				redeem_amount,
 				fee_amount,
+				lp_fee_amount,
 				balances,
 				total_supply,
 				burn_amount,
@@ -1972,7 +4635,8 @@ impl<T: Config> StableAsset for Pallet<T> {
 			)?;
 
 			pool_info.total_supply = total_supply;
-			pool_info.balances = balances;
+			pool_info.pool_token_supply = Self::sub_pool_token_supply(pool_info.pool_token_supply, burn_amount)?;
+			pool_info.balances = balances.try_into().map_err(|_| Error::<T>::InconsistentStorage)?;
 			Self::collect_fee(pool_id, pool_info)?;
 			let a: T::AtLeast64BitUnsigned = Self::get_a(
 				pool_info.a,
@@ -1987,15 +4651,136 @@ impl<T: Config> StableAsset for Pallet<T> {
 				a,
 				output_amounts: amounts,
 				max_input_amount: max_redeem_amount,
-				balances: pool_info.balances.clone(),
+				balances: pool_info.balances.to_vec(),
 				total_supply: pool_info.total_supply,
 				fee_amount,
+				lp_fee_amount,
 				input_amount: redeem_amount,
 			});
 			Ok(())
 		})
 	}
 
+	/// Swap tokens along a route of pools, atomically
+	///
+	/// # Arguments
+	///
+	/// * `route` - the steps of the route, the output asset of a step is the input asset of the next
+	/// * `input_amount` - the amount of input token of the first step
+	/// * `min_output_amount` - the minimum amount of output token of the last step received
+	fn swap_route(
+		who: &Self::AccountId,
+		route: Vec<SwapRouteStep>,
+		input_amount: Self::Balance,
+		min_output_amount: Self::Balance,
+	) -> sp_std::result::Result<Self::Balance, DispatchError> {
+		ensure!(
+			!route.is_empty() && route.len() <= T::MaxRouteLength::get() as usize,
+			Error::<T>::InvalidRoute
+		);
+		// validate the whole route before executing any step
+		let mut maybe_assets: Option<(T::AssetId, T::AssetId)> = None;
+		for (index, step) in route.iter().enumerate() {
+			// each pool is used at most once in a route
+			ensure!(
+				route[..index]
+					.iter()
+					.all(|previous| previous.pool_id() != step.pool_id()),
+				Error::<T>::InvalidRoute
+			);
+			let pool_info = Self::pool(step.pool_id()).ok_or(Error::<T>::PoolNotFound)?;
+			let (step_input_asset, step_output_asset) = Self::get_route_step_assets(&pool_info, step)?;
+			let input_asset = match maybe_assets {
+				Some((input_asset, previous_output_asset)) => {
+					ensure!(previous_output_asset == step_input_asset, Error::<T>::InvalidRoute);
+					input_asset
+				}
+				None => step_input_asset,
+			};
+			maybe_assets = Some((input_asset, step_output_asset));
+		}
+		let (input_asset, output_asset) = maybe_assets.ok_or(Error::<T>::InvalidRoute)?;
+
+		with_storage_layer(|| -> sp_std::result::Result<Self::Balance, DispatchError> {
+			let mut amount = input_amount;
+			for step in route.iter() {
+				let pool_id = step.pool_id();
+				let pool_info = Self::pool(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+				amount = match *step {
+					SwapRouteStep::Swap {
+						input_index,
+						output_index,
+						..
+					} => {
+						let asset_length = pool_info.assets.len() as u32;
+						let (_, dy) = <Self as StableAsset>::swap(
+							who,
+							pool_id,
+							input_index,
+							output_index,
+							amount,
+							Zero::zero(),
+							asset_length,
+						)?;
+						dy
+					}
+					SwapRouteStep::Mint { input_index, .. } => {
+						let mut amounts: Vec<T::Balance> = vec![Zero::zero(); pool_info.assets.len()];
+						amounts[input_index as usize] = amount;
+						let MintResult { mint_amount, .. } = Self::get_mint_amount(&pool_info, &amounts)?;
+						<Self as StableAsset>::mint(who, pool_id, amounts, mint_amount)?;
+						mint_amount
+					}
+					SwapRouteStep::RedeemSingle { output_index, .. } => {
+						let asset_length = pool_info.assets.len() as u32;
+						let (_, dy) = <Self as StableAsset>::redeem_single(
+							who,
+							pool_id,
+							amount,
+							output_index,
+							Zero::zero(),
+							asset_length,
+						)?;
+						dy
+					}
+				};
+			}
+			ensure!(amount >= min_output_amount, Error::<T>::SwapUnderMin);
+			Self::deposit_event(Event::RouteSwapped {
+				swapper: who.clone(),
+				route,
+				input_asset,
+				output_asset,
+				input_amount,
+				min_output_amount,
+				output_amount: amount,
+			});
+			Ok(amount)
+		})
+	}
+
+	/// Swap between the underlying assets of a metapool through its base pool
+	///
+	/// # Arguments
+	///
+	/// * `who` - the account swapping
+	/// * `pool_id` - the ID of the metapool
+	/// * `i` - the underlying index of the input asset
+	/// * `j` - the underlying index of the output asset
+	/// * `dx` - the amount of input asset
+	/// * `min_dy` - the minimum amount of output asset received
+	fn swap_underlying(
+		who: &Self::AccountId,
+		pool_id: StableAssetPoolId,
+		i: PoolTokenIndex,
+		j: PoolTokenIndex,
+		dx: Self::Balance,
+		min_dy: Self::Balance,
+	) -> sp_std::result::Result<Self::Balance, DispatchError> {
+		let route = Self::get_underlying_route(pool_id, i, j)?;
+		<Self as StableAsset>::swap_route(who, route, dx, min_dy)
+	}
+
 	/// Modify A value
 	///
 	/// # Arguments
@@ -2010,8 +4795,15 @@ impl<T: Config> StableAsset for Pallet<T> {
 	) -> DispatchResult {
 		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
 			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
-			ensure!(future_a_block > pool_info.a_block, Error::<T>::ArgumentsError);
+			ensure!(a <= T::MaxA::get(), Error::<T>::AOverMax);
 			let current_block = frame_system::Pallet::<T>::block_number();
+			let min_future_a_block = current_block
+				.checked_add(&T::MinRampBlocks::get())
+				.ok_or(Error::<T>::Math)?;
+			ensure!(
+				future_a_block > current_block && future_a_block >= min_future_a_block,
+				Error::<T>::RampTooShort
+			);
 			let initial_a: T::AtLeast64BitUnsigned = Self::get_a(
 				pool_info.a,
 				pool_info.a_block,
@@ -2019,6 +4811,13 @@ impl<T: Config> StableAsset for Pallet<T> {
 				pool_info.future_a_block,
 			)
 			.ok_or(Error::<T>::Math)?;
+			// a product overflowing the type is beyond any change factor
+			let max_change = T::MaxAChangeFactor::get();
+			ensure!(
+				a.checked_mul(&max_change).is_none_or(|x| x >= initial_a)
+					&& initial_a.checked_mul(&max_change).is_none_or(|x| x >= a),
+				Error::<T>::AChangeTooLarge
+			);
 			pool_info.a = initial_a;
 			pool_info.a_block = current_block;
 			pool_info.future_a = a;
@@ -2032,6 +4831,268 @@ impl<T: Config> StableAsset for Pallet<T> {
 		})
 	}
 
+	/// Stop the A ramp of a pool, keeping A at its value at the current block
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	fn stop_ramp_a(pool_id: StableAssetPoolId) -> DispatchResult {
+		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
+			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+			let current_block = frame_system::Pallet::<T>::block_number();
+			let current_a: T::AtLeast64BitUnsigned = Self::get_a(
+				pool_info.a,
+				pool_info.a_block,
+				pool_info.future_a,
+				pool_info.future_a_block,
+			)
+			.ok_or(Error::<T>::Math)?;
+			pool_info.a = current_a;
+			pool_info.a_block = current_block;
+			pool_info.future_a = current_a;
+			pool_info.future_a_block = current_block;
+			Self::deposit_event(Event::RampStopped {
+				pool_id,
+				a: current_a,
+				time: current_block,
+			});
+			Ok(())
+		})
+	}
+
+	/// Append an asset to a pool
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	/// * `asset` - the asset to add
+	/// * `precision` - 10**precision / 10**asset_precision
+	fn add_asset(
+		pool_id: StableAssetPoolId,
+		asset: Self::AssetId,
+		precision: Self::AtLeast64BitUnsigned,
+	) -> DispatchResult {
+		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
+			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+			ensure!(
+				asset != pool_info.pool_asset && !pool_info.assets.contains(&asset),
+				Error::<T>::ArgumentsError
+			);
+			// yield and fees pending so far are collected over the current assets
+			Self::collect_yield(pool_id, pool_info)?;
+			Self::collect_fee(pool_id, pool_info)?;
+			let balance = T::Assets::balance(asset, &pool_info.account_id);
+			let has_liquidity = !pool_info.total_supply.is_zero();
+			ensure!(!has_liquidity || !balance.is_zero(), Error::<T>::AssetNotFunded);
+			pool_info
+				.assets
+				.try_push(asset)
+				.map_err(|_| Error::<T>::ArgumentsError)?;
+			pool_info
+				.precisions
+				.try_push(precision)
+				.map_err(|_| Error::<T>::InconsistentStorage)?;
+			pool_info
+				.balances
+				.try_push(Zero::zero())
+				.map_err(|_| Error::<T>::InconsistentStorage)?;
+			if has_liquidity {
+				// the D added by the asset is minted as yield
+				let old_total_supply = pool_info.total_supply;
+				Self::collect_yield(pool_id, pool_info)?;
+				ensure!(pool_info.total_supply >= old_total_supply, Error::<T>::AssetNotFunded);
+			}
+			Self::update_oracle(pool_id, pool_info);
+			Self::deposit_event(Event::AssetAdded {
+				pool_id,
+				asset,
+				precision,
+				balance,
+			});
+			Ok(())
+		})
+	}
+
+	/// Remove a drained asset from a pool, sweeping its remaining balance to `DustAccount`
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	/// * `index` - the index of the asset to remove
+	fn remove_asset(pool_id: StableAssetPoolId, index: PoolTokenIndex) -> DispatchResult {
+		Pools::<T>::try_mutate_exists(pool_id, |maybe_pool_info| -> DispatchResult {
+			let pool_info = maybe_pool_info.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+			let index_usize = index as usize;
+			ensure!(
+				index_usize < pool_info.assets.len() && pool_info.assets.len() > 2,
+				Error::<T>::ArgumentsError
+			);
+			let restrictions = Restrictions::<T>::get(pool_id);
+			ensure!(restrictions.is_input_disabled(index), Error::<T>::AssetNotWoundDown);
+			// yield and fees pending so far are collected over the current assets
+			Self::collect_yield(pool_id, pool_info)?;
+			Self::collect_fee(pool_id, pool_info)?;
+			let a: T::AtLeast64BitUnsigned = Self::get_a(
+				pool_info.a,
+				pool_info.a_block,
+				pool_info.future_a,
+				pool_info.future_a_block,
+			)
+			.ok_or(Error::<T>::Math)?;
+			// a metapool removing its base pool token is no longer a metapool
+			if let Some((base_index, base_pool_id)) = Self::get_base_pool(pool_info) {
+				if base_index == index {
+					BasePools::<T>::remove(pool_id);
+					MetaPools::<T>::remove(base_pool_id, pool_id);
+				}
+			}
+			ensure!(
+				pool_info.balances[index_usize] <= T::AssetDustThreshold::get(),
+				Error::<T>::AssetNotDrained
+			);
+			let asset = pool_info.assets.remove(index_usize);
+			pool_info.precisions.remove(index_usize);
+			pool_info.balances.remove(index_usize);
+			PoolRates::<T>::mutate_exists(pool_id, |maybe_rates| {
+				if let Some(rates) = maybe_rates.as_mut().filter(|rates| index_usize < rates.len()) {
+					rates.remove(index_usize);
+				}
+			});
+			let balances: Vec<T::AtLeast64BitUnsigned> =
+				Self::convert_vec_balance_to_number(pool_info.balances.to_vec());
+			// the pool token supply is kept: the liquidity providers share the change in D, losing the dust
+			// but no longer the imbalance it held D back by
+			let new_d: T::AtLeast64BitUnsigned = Self::get_d(&balances, a).ok_or(Error::<T>::Math)?;
+			let dust_account = T::DustAccount::get();
+			let amount = T::Assets::balance(asset, &pool_info.account_id);
+			if !amount.is_zero() {
+				T::Assets::transfer(
+					asset,
+					&pool_info.account_id,
+					&dust_account,
+					amount,
+					Preservation::Expendable,
+				)?;
+			}
+			pool_info.total_supply = new_d.into();
+			Restrictions::<T>::mutate(pool_id, |restrictions| {
+				*restrictions = restrictions.without_index(index);
+			});
+			Self::update_oracle(pool_id, pool_info);
+			Self::deposit_event(Event::AssetRemoved {
+				pool_id,
+				asset,
+				who: dust_account,
+				amount,
+			});
+			Ok(())
+		})
+	}
+
+	/// Start destroying a pool, allowing only redeems
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	fn start_destroy_pool(pool_id: StableAssetPoolId) -> DispatchResult {
+		ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotFound);
+		ensure!(
+			PoolStatuses::<T>::get(pool_id) != PoolStatus::Destroying,
+			Error::<T>::PoolDestroying
+		);
+		PoolStatuses::<T>::insert(pool_id, PoolStatus::Destroying);
+		Self::deposit_event(Event::PoolDestroying { pool_id });
+		Ok(())
+	}
+
+	/// Remove a pool being destroyed, sweeping the assets left in its account to `DustAccount`. The pool
+	/// tokens left, at most `PoolDustThreshold`, stay outstanding, and the pool asset cannot back a new pool
+	/// until they are burnt.
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	fn destroy_pool(pool_id: StableAssetPoolId) -> DispatchResult {
+		let pool_info = Pools::<T>::get(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+		ensure!(
+			PoolStatuses::<T>::get(pool_id) == PoolStatus::Destroying,
+			Error::<T>::PoolNotDestroying
+		);
+		// metapools value the pool token at the virtual price of this pool
+		ensure!(
+			MetaPools::<T>::iter_key_prefix(pool_id).next().is_none(),
+			Error::<T>::PoolAssetInUse
+		);
+		ensure!(
+			pool_info.pool_token_supply <= T::PoolDustThreshold::get(),
+			Error::<T>::PoolNotDrained
+		);
+		let dust_account = T::DustAccount::get();
+		let mut amounts: Vec<T::Balance> = Vec::new();
+		for asset in pool_info.assets.iter() {
+			let amount = T::Assets::balance(*asset, &pool_info.account_id);
+			if !amount.is_zero() {
+				T::Assets::transfer(
+					*asset,
+					&pool_info.account_id,
+					&dust_account,
+					amount,
+					Preservation::Expendable,
+				)?;
+			}
+			amounts.push(amount);
+		}
+		Pools::<T>::remove(pool_id);
+		PoolStatuses::<T>::remove(pool_id);
+		Restrictions::<T>::remove(pool_id);
+		PoolAssetPools::<T>::remove(pool_info.pool_asset);
+		Oracles::<T>::remove(pool_id);
+		let _ = OracleObservations::<T>::clear_prefix(pool_id, u32::MAX, None);
+		EmaHalfLives::<T>::remove(pool_id);
+		EmaPrices::<T>::remove(pool_id);
+		PoolRates::<T>::remove(pool_id);
+		if let Some(base_pool_id) = BasePools::<T>::take(pool_id) {
+			MetaPools::<T>::remove(base_pool_id, pool_id);
+		}
+		frame_system::Pallet::<T>::dec_providers(&pool_info.account_id)?;
+		Self::deposit_event(Event::PoolDestroyed {
+			pool_id,
+			who: dust_account,
+			amounts,
+		});
+		Ok(())
+	}
+
+	/// Make a pool holding the pool token of a base pool a metapool of it
+	///
+	/// # Arguments
+	///
+	/// * `pool_id` - the ID of the pool
+	/// * `base_pool_id` - the ID of the base pool
+	fn set_base_pool(pool_id: StableAssetPoolId, base_pool_id: StableAssetPoolId) -> DispatchResult {
+		let pool_info = Pools::<T>::get(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+		let base_pool_info = Pools::<T>::get(base_pool_id).ok_or(Error::<T>::PoolNotFound)?;
+		ensure!(
+			pool_id != base_pool_id
+				&& !BasePools::<T>::contains_key(pool_id)
+				&& pool_info.assets.contains(&base_pool_info.pool_asset),
+			Error::<T>::ArgumentsError
+		);
+		ensure!(
+			PoolStatuses::<T>::get(base_pool_id) != PoolStatus::Destroying,
+			Error::<T>::PoolDestroying
+		);
+		// liquidity added before is valued at one base pool token per D
+		ensure!(
+			pool_info.total_supply.is_zero() && pool_info.pool_token_supply.is_zero(),
+			Error::<T>::PoolNotEmpty
+		);
+		BasePools::<T>::insert(pool_id, base_pool_id);
+		MetaPools::<T>::insert(base_pool_id, pool_id, ());
+		Self::deposit_event(Event::BasePoolSet { pool_id, base_pool_id });
+		Ok(())
+	}
+
 	fn get_collect_yield_amount(
 		pool_info: &StableAssetPoolInfo<
 			T::AssetId,
@@ -2039,9 +5100,18 @@ impl<T: Config> StableAsset for Pallet<T> {
 			T::Balance,
 			T::AccountId,
 			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
+		>,
+	) -> Option<
+		StableAssetPoolInfo<
+			T::AssetId,
+			T::AtLeast64BitUnsigned,
+			T::Balance,
+			T::AccountId,
+			BlockNumberFor<T>,
+			PoolAssetLimitOf<T>,
 		>,
-	) -> Option<StableAssetPoolInfo<T::AssetId, T::AtLeast64BitUnsigned, T::Balance, T::AccountId, BlockNumberFor<T>>>
-	{
+	> {
 		Self::get_collect_yield_amount(pool_info).ok()
 	}
 
@@ -2052,6 +5122,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	) -> Option<
 		StableAssetPoolInfo<
@@ -2060,6 +5131,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 	> {
 		Self::get_balance_update_amount(pool_info).ok()
@@ -2072,6 +5144,7 @@ impl<T: Config> StableAsset for Pallet<T> {
 			Self::Balance,
 			Self::AccountId,
 			Self::BlockNumber,
+			Self::PoolAssetLimit,
 		>,
 		amount_bal: Self::Balance,
 	) -> Option<RedeemProportionResult<T::Balance>> {
@@ -2099,6 +5172,14 @@ impl<T: Config> StableAsset for Pallet<T> {
 				.map(|usize_index| usize_index as PoolTokenIndex);
 
 			if let (Some(input_index), Some(output_index)) = (maybe_input_index, maybe_output_index) {
+				let step = SwapRouteStep::Swap {
+					pool_id,
+					input_index,
+					output_index,
+				};
+				if Self::ensure_route_step_allowed(&step).is_err() {
+					continue;
+				}
 				// calculate swap amount
 				if let Ok(swap_result) = Self::get_swap_amount(&pool_info, input_index, output_index, input_amount) {
 					// make sure pool can affort the output amount
@@ -2119,6 +5200,36 @@ impl<T: Config> StableAsset for Pallet<T> {
 		maybe_best
 	}
 
+	fn get_best_multi_hop_route(
+		input_asset: Self::AssetId,
+		output_asset: Self::AssetId,
+		input_amount: Self::Balance,
+	) -> Option<(Vec<SwapRouteStep>, Self::Balance)> {
+		if input_asset == output_asset {
+			return None;
+		}
+		let pools: Vec<_> = Pools::<T>::iter().collect();
+		let mut pools_by_asset: BTreeMap<T::AssetId, Vec<usize>> = BTreeMap::new();
+		for (index, (_, pool_info)) in pools.iter().enumerate() {
+			for asset in pool_info.assets.iter().chain(sp_std::iter::once(&pool_info.pool_asset)) {
+				pools_by_asset.entry(*asset).or_default().push(index);
+			}
+		}
+		let mut best: Option<(Vec<SwapRouteStep>, Self::Balance)> = None;
+		Self::search_route(
+			&pools_by_asset,
+			&pools,
+			input_asset,
+			output_asset,
+			input_amount,
+			T::MaxRouteLength::get(),
+			&mut Vec::new(),
+			&mut vec![input_asset],
+			&mut best,
+		);
+		best
+	}
+
 	fn get_swap_output_amount(
 		pool_id: StableAssetPoolId,
 		input_index: PoolTokenIndex,
@@ -2144,4 +5255,98 @@ impl<T: Config> StableAsset for Pallet<T> {
 			None => None,
 		}
 	}
+
+	fn get_mint_output_amount(
+		pool_id: StableAssetPoolId,
+		amounts: Vec<Self::Balance>,
+	) -> Option<MintResult<Self::Balance>> {
+		let pool_info_opt = Self::pool(pool_id);
+		match pool_info_opt {
+			Some(pool_info) => Self::get_mint_amount(&pool_info, &amounts).ok(),
+			None => None,
+		}
+	}
+
+	fn get_redeem_proportion_output_amount(
+		pool_id: StableAssetPoolId,
+		amount: Self::Balance,
+	) -> Option<RedeemProportionResult<Self::Balance>> {
+		let pool_info_opt = Self::pool(pool_id);
+		match pool_info_opt {
+			Some(pool_info) => Self::get_redeem_proportion_amount(&pool_info, amount).ok(),
+			None => None,
+		}
+	}
+
+	fn get_redeem_single_output_amount(
+		pool_id: StableAssetPoolId,
+		amount: Self::Balance,
+		output_index: PoolTokenIndex,
+	) -> Option<RedeemSingleResult<Self::Balance>> {
+		let pool_info_opt = Self::pool(pool_id);
+		match pool_info_opt {
+			Some(pool_info) => Self::get_redeem_single_amount(&pool_info, amount, output_index).ok(),
+			None => None,
+		}
+	}
+
+	fn get_redeem_multi_input_amount(
+		pool_id: StableAssetPoolId,
+		amounts: Vec<Self::Balance>,
+	) -> Option<RedeemMultiResult<Self::Balance>> {
+		let pool_info_opt = Self::pool(pool_id);
+		match pool_info_opt {
+			Some(pool_info) => Self::get_redeem_multi_amount(&pool_info, &amounts).ok(),
+			None => None,
+		}
+	}
+
+	fn get_current_a(pool_id: StableAssetPoolId) -> Option<Self::AtLeast64BitUnsigned> {
+		let pool_info_opt = Self::pool(pool_id);
+		match pool_info_opt {
+			Some(pool_info) => Self::get_a(
+				pool_info.a,
+				pool_info.a_block,
+				pool_info.future_a,
+				pool_info.future_a_block,
+			),
+			None => None,
+		}
+	}
+
+	fn get_virtual_price(pool_id: StableAssetPoolId) -> Option<Self::AtLeast64BitUnsigned> {
+		let pool_info = Self::pool(pool_id)?;
+		Self::get_pool_virtual_price(&pool_info, pool_info.precision)
+	}
+
+	fn get_twap(
+		pool_id: StableAssetPoolId,
+		window: Self::BlockNumber,
+	) -> Option<(Vec<Self::AtLeast64BitUnsigned>, Self::AtLeast64BitUnsigned)> {
+		if window.is_zero() {
+			return None;
+		}
+		let oracle = Oracles::<T>::get(pool_id)?;
+		let current_block = frame_system::Pallet::<T>::block_number();
+		let start_block = current_block.checked_sub(&window)?;
+		let (end_prices, end_virtual_price) = Self::get_oracle_cumulatives(pool_id, &oracle, current_block)?;
+		let (start_prices, start_virtual_price) = Self::get_oracle_cumulatives(pool_id, &oracle, start_block)?;
+		let window = T::AtLeast64BitUnsigned::from(window);
+		let prices = end_prices
+			.into_iter()
+			.zip(start_prices)
+			.map(|(end, start)| end.checked_sub(&start)?.checked_div(&window))
+			.collect::<Option<Vec<_>>>()?;
+		let virtual_price = end_virtual_price
+			.checked_sub(&start_virtual_price)?
+			.checked_div(&window)?;
+		Some((prices, virtual_price))
+	}
+
+	fn get_ema_prices(pool_id: StableAssetPoolId) -> Option<Vec<Self::AtLeast64BitUnsigned>> {
+		let half_life = EmaHalfLives::<T>::get(pool_id)?;
+		let oracle = Oracles::<T>::get(pool_id)?;
+		let current_block = frame_system::Pallet::<T>::block_number();
+		Self::get_pool_ema_prices(pool_id, &oracle, half_life, current_block).map(|ema_prices| ema_prices.into_inner())
+	}
 }
@@ -0,0 +1,29 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage migrations for the stable asset pallet.
+//!
+//! Every storage version bump gets its own module exporting a `VersionedMigration`, which only runs
+//! when the on-chain storage version matches and bumps it afterwards. Runtimes add the exported
+//! migrations to the migrations tuple of their `Executive` in order.
+
+pub mod v1;
+pub mod v2;
+pub mod v3;
+pub mod v4;
+
+const LOG_TARGET: &str = "runtime::stable-asset";
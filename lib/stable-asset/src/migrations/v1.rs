@@ -0,0 +1,205 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Migrate `Pools` from unbounded vectors to vectors bounded by `PoolAssetLimit`.
+
+use super::LOG_TARGET;
+use crate::*;
+use frame_support::{migrations::VersionedMigration, pallet_prelude::*, traits::UncheckedOnRuntimeUpgrade};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+#[derive(Encode, Decode)]
+pub struct OldStableAssetPoolInfo<AssetId, AtLeast64BitUnsigned, Balance, AccountId, BlockNumber> {
+	pub pool_asset: AssetId,
+	pub assets: Vec<AssetId>,
+	pub precisions: Vec<AtLeast64BitUnsigned>,
+	pub mint_fee: AtLeast64BitUnsigned,
+	pub swap_fee: AtLeast64BitUnsigned,
+	pub redeem_fee: AtLeast64BitUnsigned,
+	pub total_supply: Balance,
+	pub a: AtLeast64BitUnsigned,
+	pub a_block: BlockNumber,
+	pub future_a: AtLeast64BitUnsigned,
+	pub future_a_block: BlockNumber,
+	pub balances: Vec<Balance>,
+	pub fee_recipient: AccountId,
+	pub account_id: AccountId,
+	pub yield_recipient: AccountId,
+	pub precision: AtLeast64BitUnsigned,
+}
+
+pub type OldStableAssetPoolInfoOf<T> = OldStableAssetPoolInfo<
+	<T as Config>::AssetId,
+	<T as Config>::AtLeast64BitUnsigned,
+	<T as Config>::Balance,
+	<T as frame_system::Config>::AccountId,
+	BlockNumberFor<T>,
+>;
+
+/// The layout of `StableAssetPoolInfo` at storage version 1.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct StableAssetPoolInfoV1<AssetId, AtLeast64BitUnsigned, Balance, AccountId, BlockNumber, PoolAssetLimit>
+where
+	PoolAssetLimit: Get<u32>,
+{
+	pub pool_asset: AssetId,
+	pub assets: BoundedVec<AssetId, PoolAssetLimit>,
+	pub precisions: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
+	pub mint_fee: AtLeast64BitUnsigned,
+	pub swap_fee: AtLeast64BitUnsigned,
+	pub redeem_fee: AtLeast64BitUnsigned,
+	pub total_supply: Balance,
+	pub a: AtLeast64BitUnsigned,
+	pub a_block: BlockNumber,
+	pub future_a: AtLeast64BitUnsigned,
+	pub future_a_block: BlockNumber,
+	pub balances: BoundedVec<Balance, PoolAssetLimit>,
+	pub fee_recipient: AccountId,
+	pub account_id: AccountId,
+	pub yield_recipient: AccountId,
+	pub precision: AtLeast64BitUnsigned,
+}
+
+pub(crate) type StableAssetPoolInfoV1Of<T> = StableAssetPoolInfoV1<
+	<T as Config>::AssetId,
+	<T as Config>::AtLeast64BitUnsigned,
+	<T as Config>::Balance,
+	<T as frame_system::Config>::AccountId,
+	BlockNumberFor<T>,
+	PoolAssetLimitOf<T>,
+>;
+
+/// `Pools` with the layout of storage version 1, which this migration writes.
+#[frame_support::storage_alias]
+pub(crate) type Pools<T: Config> =
+	StorageMap<Pallet<T>, Blake2_128Concat, StableAssetPoolId, StableAssetPoolInfoV1Of<T>>;
+
+/// Pools over `PoolAssetLimit` assets, moved out of `Pools` with the layout before storage version 1 so
+/// that later migrations of `Pools` can decode every pool. A later runtime upgrade which raises
+/// `PoolAssetLimit` to cover them can move them back.
+#[frame_support::storage_alias]
+pub type OversizedPools<T: Config> =
+	StorageMap<Pallet<T>, Blake2_128Concat, StableAssetPoolId, OldStableAssetPoolInfoOf<T>>;
+
+mod old {
+	use super::*;
+
+	/// `Pools` with the layout before storage version 1, which this migration reads.
+	#[frame_support::storage_alias]
+	pub(super) type Pools<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, StableAssetPoolId, OldStableAssetPoolInfoOf<T>>;
+}
+
+mod version_unchecked {
+	use super::*;
+
+	/// Check every pool fits in bounded vectors. The encoding of a bounded vector is the same as the
+	/// encoding of a vector, so pools are left as they are.
+	///
+	/// The runtime must raise `PoolAssetLimit` to cover every pool before running this migration, and
+	/// `pre_upgrade` fails on a pool with more assets. A pool with more assets could never be decoded from
+	/// `Pools`: if the migration runs anyway, it is moved to `OversizedPools`, along with its state, and
+	/// logged as an error.
+	pub struct UncheckedMigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let limit = T::PoolAssetLimit::get() as usize;
+			let mut reads: u64 = 0;
+			let mut oversized: Vec<(StableAssetPoolId, OldStableAssetPoolInfoOf<T>)> = Vec::new();
+			for (pool_id, old) in old::Pools::<T>::iter() {
+				reads = reads.saturating_add(1);
+				if old.assets.len() > limit || old.precisions.len() > limit || old.balances.len() > limit {
+					oversized.push((pool_id, old));
+				}
+			}
+			let writes = (oversized.len() as u64).saturating_mul(2);
+			for (pool_id, old) in oversized {
+				log::error!(
+					target: LOG_TARGET,
+					"pool {} has more than PoolAssetLimit assets, moved to OversizedPools",
+					pool_id
+				);
+				old::Pools::<T>::remove(pool_id);
+				OversizedPools::<T>::insert(pool_id, old);
+			}
+
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		/// Snapshot the pool count and every pool, as decoded with the old layout, and make sure every pool
+		/// fits the bound.
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let limit = T::PoolAssetLimit::get() as usize;
+			let mut pools: Vec<(StableAssetPoolId, OldStableAssetPoolInfoOf<T>)> = Vec::new();
+			for pool_id in old::Pools::<T>::iter_keys() {
+				let old = old::Pools::<T>::get(pool_id).ok_or("pool cannot be decoded with the old layout")?;
+				ensure!(
+					old.assets.len() <= limit && old.precisions.len() <= limit && old.balances.len() <= limit,
+					"pool has more than PoolAssetLimit assets"
+				);
+				pools.push((pool_id, old));
+			}
+			Ok((PoolCount::<T>::get(), pools).encode())
+		}
+
+		/// Check the pool count is unchanged, and every pool kept its state.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let (pool_count, pools): (StableAssetPoolId, Vec<(StableAssetPoolId, OldStableAssetPoolInfoOf<T>)>) =
+				Decode::decode(&mut &state[..]).map_err(|_| "failed to decode the pre upgrade state")?;
+			ensure!(PoolCount::<T>::get() == pool_count, "pool count changed");
+			ensure!(
+				Pools::<T>::iter_keys().count() == pools.len(),
+				"unexpected pools after the upgrade"
+			);
+
+			for (pool_id, old) in pools {
+				ensure!(pool_id < pool_count, "pool id is not below the pool count");
+				let pool_info = Pools::<T>::get(pool_id).ok_or("pool was removed")?;
+				ensure!(
+					pool_info.assets.len() == pool_info.precisions.len()
+						&& pool_info.assets.len() == pool_info.balances.len(),
+					"pool vectors have different lengths"
+				);
+				ensure!(
+					pool_info.pool_asset == old.pool_asset
+						&& pool_info.assets[..] == old.assets[..]
+						&& pool_info.precisions[..] == old.precisions[..]
+						&& pool_info.balances[..] == old.balances[..]
+						&& pool_info.total_supply == old.total_supply
+						&& pool_info.account_id == old.account_id,
+					"pool state changed"
+				);
+			}
+			Ok(())
+		}
+	}
+}
+
+/// Migrate `Pools` to storage version 1, see `UncheckedMigrateToV1`.
+pub type MigrateToV1<T> = VersionedMigration<
+	0,
+	1,
+	version_unchecked::UncheckedMigrateToV1<T>,
+	Pallet<T>,
+	<T as frame_system::Config>::DbWeight,
+>;
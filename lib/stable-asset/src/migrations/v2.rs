@@ -0,0 +1,163 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Migrate `Pools` to the layout with `offpeg_fee_multiplier`, which is zero for existing pools.
+
+use super::v1::{StableAssetPoolInfoV1, StableAssetPoolInfoV1Of};
+use crate::*;
+use frame_support::{migrations::VersionedMigration, pallet_prelude::*, traits::UncheckedOnRuntimeUpgrade};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+/// The layout of `StableAssetPoolInfo` at storage version 2.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct StableAssetPoolInfoV2<AssetId, AtLeast64BitUnsigned, Balance, AccountId, BlockNumber, PoolAssetLimit>
+where
+	PoolAssetLimit: Get<u32>,
+{
+	pub pool_asset: AssetId,
+	pub assets: BoundedVec<AssetId, PoolAssetLimit>,
+	pub precisions: BoundedVec<AtLeast64BitUnsigned, PoolAssetLimit>,
+	pub mint_fee: AtLeast64BitUnsigned,
+	pub swap_fee: AtLeast64BitUnsigned,
+	pub redeem_fee: AtLeast64BitUnsigned,
+	pub offpeg_fee_multiplier: AtLeast64BitUnsigned,
+	pub total_supply: Balance,
+	pub a: AtLeast64BitUnsigned,
+	pub a_block: BlockNumber,
+	pub future_a: AtLeast64BitUnsigned,
+	pub future_a_block: BlockNumber,
+	pub balances: BoundedVec<Balance, PoolAssetLimit>,
+	pub fee_recipient: AccountId,
+	pub account_id: AccountId,
+	pub yield_recipient: AccountId,
+	pub precision: AtLeast64BitUnsigned,
+}
+
+pub(crate) type StableAssetPoolInfoV2Of<T> = StableAssetPoolInfoV2<
+	<T as Config>::AssetId,
+	<T as Config>::AtLeast64BitUnsigned,
+	<T as Config>::Balance,
+	<T as frame_system::Config>::AccountId,
+	BlockNumberFor<T>,
+	PoolAssetLimitOf<T>,
+>;
+
+/// `Pools` with the layout of storage version 2, which this migration writes.
+#[frame_support::storage_alias]
+pub(crate) type Pools<T: Config> =
+	StorageMap<Pallet<T>, Blake2_128Concat, StableAssetPoolId, StableAssetPoolInfoV2Of<T>>;
+
+mod version_unchecked {
+	use super::*;
+
+	/// Re-encode every pool with a flat swap fee, i.e. a zero `offpeg_fee_multiplier`.
+	pub struct UncheckedMigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut translated: u64 = 0;
+			Pools::<T>::translate::<StableAssetPoolInfoV1Of<T>, _>(|_, old| {
+				translated = translated.saturating_add(1);
+				let StableAssetPoolInfoV1 {
+					pool_asset,
+					assets,
+					precisions,
+					mint_fee,
+					swap_fee,
+					redeem_fee,
+					total_supply,
+					a,
+					a_block,
+					future_a,
+					future_a_block,
+					balances,
+					fee_recipient,
+					account_id,
+					yield_recipient,
+					precision,
+				} = old;
+				Some(StableAssetPoolInfoV2 {
+					pool_asset,
+					assets,
+					precisions,
+					mint_fee,
+					swap_fee,
+					redeem_fee,
+					offpeg_fee_multiplier: Zero::zero(),
+					total_supply,
+					a,
+					a_block,
+					future_a,
+					future_a_block,
+					balances,
+					fee_recipient,
+					account_id,
+					yield_recipient,
+					precision,
+				})
+			});
+
+			T::DbWeight::get().reads_writes(translated, translated)
+		}
+
+		/// Snapshot every pool, as decoded with the old layout.
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let mut pools: Vec<(StableAssetPoolId, StableAssetPoolInfoV1Of<T>)> = Vec::new();
+			for pool_id in Pools::<T>::iter_keys() {
+				let old = crate::migrations::v1::Pools::<T>::get(pool_id)
+					.ok_or("pool cannot be decoded with the old layout")?;
+				pools.push((pool_id, old));
+			}
+			Ok(pools.encode())
+		}
+
+		/// Check every pool kept its state and has a flat swap fee.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let pools: Vec<(StableAssetPoolId, StableAssetPoolInfoV1Of<T>)> =
+				Decode::decode(&mut &state[..]).map_err(|_| "failed to decode the pre upgrade state")?;
+			ensure!(Pools::<T>::iter_keys().count() == pools.len(), "pool count changed");
+			for (pool_id, old) in pools {
+				let pool_info = Pools::<T>::get(pool_id).ok_or("pool was removed")?;
+				ensure!(pool_info.offpeg_fee_multiplier.is_zero(), "pool has a dynamic swap fee");
+				ensure!(
+					pool_info.pool_asset == old.pool_asset
+						&& pool_info.assets == old.assets
+						&& pool_info.balances == old.balances
+						&& pool_info.swap_fee == old.swap_fee
+						&& pool_info.total_supply == old.total_supply
+						&& pool_info.account_id == old.account_id,
+					"pool state changed"
+				);
+			}
+			Ok(())
+		}
+	}
+}
+
+/// Migrate `Pools` to storage version 2, see `UncheckedMigrateToV2`.
+pub type MigrateToV2<T> = VersionedMigration<
+	1,
+	2,
+	version_unchecked::UncheckedMigrateToV2<T>,
+	Pallet<T>,
+	<T as frame_system::Config>::DbWeight,
+>;
@@ -0,0 +1,137 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Migrate `Pools` to the layout with `admin_fee` and `pool_token_supply`. Existing pools keep sending
+//! every fee to the fee recipient, and their pool token supply is the issuance of the pool token.
+
+use super::v2::{StableAssetPoolInfoV2, StableAssetPoolInfoV2Of};
+use crate::*;
+use frame_support::{migrations::VersionedMigration, traits::UncheckedOnRuntimeUpgrade};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+mod version_unchecked {
+	use super::*;
+
+	/// Re-encode every pool with an `admin_fee` of `FeePrecision` and a `pool_token_supply` read from the
+	/// issuance of its pool token.
+	pub struct UncheckedMigrateToV3<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut translated: u64 = 0;
+			Pools::<T>::translate::<StableAssetPoolInfoV2Of<T>, _>(|_, old| {
+				translated = translated.saturating_add(1);
+				let StableAssetPoolInfoV2 {
+					pool_asset,
+					assets,
+					precisions,
+					mint_fee,
+					swap_fee,
+					redeem_fee,
+					offpeg_fee_multiplier,
+					total_supply,
+					a,
+					a_block,
+					future_a,
+					future_a_block,
+					balances,
+					fee_recipient,
+					account_id,
+					yield_recipient,
+					precision,
+				} = old;
+				Some(StableAssetPoolInfo {
+					pool_asset,
+					assets,
+					precisions,
+					mint_fee,
+					swap_fee,
+					redeem_fee,
+					offpeg_fee_multiplier,
+					admin_fee: T::FeePrecision::get(),
+					total_supply,
+					pool_token_supply: T::Assets::total_issuance(pool_asset),
+					a,
+					a_block,
+					future_a,
+					future_a_block,
+					balances,
+					fee_recipient,
+					account_id,
+					yield_recipient,
+					precision,
+				})
+			});
+
+			T::DbWeight::get().reads_writes(translated.saturating_mul(2), translated)
+		}
+
+		/// Snapshot every pool, as decoded with the old layout.
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			let mut pools: Vec<(StableAssetPoolId, StableAssetPoolInfoV2Of<T>)> = Vec::new();
+			for pool_id in Pools::<T>::iter_keys() {
+				let old = crate::migrations::v2::Pools::<T>::get(pool_id)
+					.ok_or("pool cannot be decoded with the old layout")?;
+				pools.push((pool_id, old));
+			}
+			Ok(pools.encode())
+		}
+
+		/// Check every pool kept its state, sends every fee to the fee recipient and tracks the issuance of
+		/// its pool token.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let pools: Vec<(StableAssetPoolId, StableAssetPoolInfoV2Of<T>)> =
+				Decode::decode(&mut &state[..]).map_err(|_| "failed to decode the pre upgrade state")?;
+			ensure!(Pools::<T>::iter_keys().count() == pools.len(), "pool count changed");
+			for (pool_id, old) in pools {
+				let pool_info = Pools::<T>::get(pool_id).ok_or("pool was removed")?;
+				ensure!(
+					pool_info.admin_fee == T::FeePrecision::get(),
+					"pool does not send every fee to the fee recipient"
+				);
+				ensure!(
+					pool_info.pool_token_supply == T::Assets::total_issuance(pool_info.pool_asset),
+					"pool token supply does not match the pool token issuance"
+				);
+				ensure!(
+					pool_info.pool_asset == old.pool_asset
+						&& pool_info.assets == old.assets
+						&& pool_info.balances == old.balances
+						&& pool_info.offpeg_fee_multiplier == old.offpeg_fee_multiplier
+						&& pool_info.total_supply == old.total_supply
+						&& pool_info.account_id == old.account_id,
+					"pool state changed"
+				);
+			}
+			Ok(())
+		}
+	}
+}
+
+/// Migrate `Pools` to storage version 3, see `UncheckedMigrateToV3`.
+pub type MigrateToV3<T> = VersionedMigration<
+	2,
+	3,
+	version_unchecked::UncheckedMigrateToV3<T>,
+	Pallet<T>,
+	<T as frame_system::Config>::DbWeight,
+>;
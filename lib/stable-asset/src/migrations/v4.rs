@@ -0,0 +1,95 @@
+// This file is part of NUTS Finance.
+
+// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Index the pool asset of every existing pool in `PoolAssetPools`. No pool becomes a metapool, so the
+//! rates of existing pools are unchanged; metapools are set up with `set_base_pool`.
+
+use super::LOG_TARGET;
+use crate::*;
+use frame_support::{migrations::VersionedMigration, traits::UncheckedOnRuntimeUpgrade};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+mod version_unchecked {
+	use super::*;
+
+	/// Insert the pool asset of every pool into `PoolAssetPools`. Should several pools share a pool asset,
+	/// the one with the lowest id is indexed.
+	pub struct UncheckedMigrateToV4<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV4<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let mut pools: Vec<(StableAssetPoolId, T::AssetId)> = Pools::<T>::iter()
+				.map(|(pool_id, pool_info)| (pool_id, pool_info.pool_asset))
+				.collect();
+			pools.sort_by_key(|(pool_id, _)| *pool_id);
+			let mut indexed: u64 = 0;
+			for (pool_id, pool_asset) in pools.iter() {
+				if PoolAssetPools::<T>::contains_key(pool_asset) {
+					log::warn!(
+						target: LOG_TARGET,
+						"pool {pool_id} shares its pool asset with another pool and is not indexed"
+					);
+					continue;
+				}
+				PoolAssetPools::<T>::insert(pool_asset, pool_id);
+				indexed = indexed.saturating_add(1);
+			}
+
+			let read = pools.len() as u64;
+			T::DbWeight::get().reads_writes(read.saturating_mul(2), indexed)
+		}
+
+		/// Check no pool asset is indexed yet.
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			ensure!(
+				PoolAssetPools::<T>::iter_keys().next().is_none(),
+				"pool assets are already indexed"
+			);
+			Ok(Vec::new())
+		}
+
+		/// Check the pool asset of every pool is indexed to a pool with that pool asset.
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			for (_, pool_info) in Pools::<T>::iter() {
+				let pool_id = PoolAssetPools::<T>::get(pool_info.pool_asset).ok_or("pool asset is not indexed")?;
+				ensure!(
+					Pools::<T>::get(pool_id).is_some_and(|indexed| indexed.pool_asset == pool_info.pool_asset),
+					"pool asset is indexed to another pool"
+				);
+			}
+			ensure!(
+				BasePools::<T>::iter_keys().next().is_none(),
+				"a pool was made a metapool"
+			);
+			Ok(())
+		}
+	}
+}
+
+/// Index the pool assets in storage version 4, see `UncheckedMigrateToV4`.
+pub type MigrateToV4<T> = VersionedMigration<
+	3,
+	4,
+	version_unchecked::UncheckedMigrateToV4<T>,
+	Pallet<T>,
+	<T as frame_system::Config>::DbWeight,
+>;
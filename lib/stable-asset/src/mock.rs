@@ -19,13 +19,14 @@ use crate as stable_asset;
 use frame_support::{
 	derive_impl,
 	dispatch::DispatchResult,
+	pallet_prelude::ValueQuery,
 	parameter_types,
 	traits::{
 		fungibles::{Dust, Inspect, Mutate, Unbalanced},
 		tokens::{DepositConsequence, Fortitude, Precision, Preservation, Provenance, WithdrawConsequence},
-		ConstU128, ConstU32, Currency, EnsureOrigin, OnUnbalanced,
+		ConstU128, ConstU32, ConstU64, EnsureOrigin,
 	},
-	PalletId,
+	Blake2_128Concat, PalletId,
 };
 use frame_system::RawOrigin;
 use sp_runtime::{traits::IdentityLookup, BuildStorage, DispatchError};
@@ -40,7 +41,7 @@ frame_support::construct_runtime!(
 	}
 );
 
-pub type AccountId = u64;
+pub type AccountId = u128;
 
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
 impl frame_system::Config for Test {
@@ -74,13 +75,17 @@ pub type AssetId = i64;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-pub struct Asset {
-	total: Balance,
-	balances: HashMap<AccountId, Balance>,
-}
+// The test assets are kept in storage, like the assets of a runtime, so that benchmarks count their accesses
+// and revert them between runs.
+#[frame_support::storage_alias]
+type NextAssetId = StorageValue<TestAssets, AssetId, ValueQuery>;
+#[frame_support::storage_alias]
+type AssetTotals = StorageMap<TestAssets, Blake2_128Concat, AssetId, Balance, ValueQuery>;
+#[frame_support::storage_alias]
+type AssetBalances = StorageDoubleMap<TestAssets, Blake2_128Concat, AssetId, Blake2_128Concat, AccountId, Balance>;
 
 thread_local! {
-	static ASSETS: RefCell<Vec<Asset>> = RefCell::new(Vec::new());
+	static RATES: RefCell<HashMap<AssetId, AtLeast64BitUnsigned>> = RefCell::new(HashMap::new());
 }
 
 pub trait CreateAssets<AssetId> {
@@ -88,38 +93,37 @@ pub trait CreateAssets<AssetId> {
 }
 
 pub struct TestAssets;
+impl TestAssets {
+	fn ensure_exists(asset: AssetId) -> DispatchResult {
+		if asset < 0 || asset >= NextAssetId::get() {
+			return Err(DispatchError::Other("Index out of range"));
+		}
+		Ok(())
+	}
+}
+
 impl CreateAssets<AssetId> for TestAssets {
 	fn create_asset() -> Result<AssetId, DispatchError> {
-		ASSETS.with(|d| -> Result<AssetId, DispatchError> {
-			let mut d = d.borrow_mut();
-			let id = AssetId::try_from(d.len()).map_err(|_| DispatchError::Other("Too large id"))?;
-			d.push(Asset {
-				total: 0,
-				balances: HashMap::new(),
-			});
-
-			Ok(id)
+		NextAssetId::try_mutate(|id| -> Result<AssetId, DispatchError> {
+			let asset = *id;
+			*id = id.checked_add(1).ok_or(DispatchError::Other("Too large id"))?;
+			Ok(asset)
 		})
 	}
 }
 
 impl Mutate<AccountId> for TestAssets {
 	fn mint_into(asset: AssetId, dest: &AccountId, amount: Balance) -> Result<Balance, DispatchError> {
-		ASSETS.with(|d| -> DispatchResult {
-			let i = usize::try_from(asset).map_err(|_| DispatchError::Other("Index out of range"))?;
-			let mut d = d.borrow_mut();
-			let a = d.get_mut(i).ok_or(DispatchError::Other("Index out of range"))?;
-
-			if let Some(x) = a.balances.get_mut(dest) {
-				*x = x.checked_add(amount).ok_or(DispatchError::Other("Overflow"))?;
-			} else {
-				a.balances.insert(*dest, amount);
-			}
-
-			a.total = a.total.checked_add(amount).ok_or(DispatchError::Other("Overflow"))?;
-
-			Ok(())
-		})?;
+		Self::ensure_exists(asset)?;
+		let balance = AssetBalances::get(asset, dest)
+			.unwrap_or_default()
+			.checked_add(amount)
+			.ok_or(DispatchError::Other("Overflow"))?;
+		let total = AssetTotals::get(asset)
+			.checked_add(amount)
+			.ok_or(DispatchError::Other("Overflow"))?;
+		AssetBalances::insert(asset, dest, balance);
+		AssetTotals::insert(asset, total);
 		Ok(amount)
 	}
 
@@ -131,19 +135,16 @@ impl Mutate<AccountId> for TestAssets {
 		_precision: Precision,
 		_fortitude: Fortitude,
 	) -> Result<Balance, DispatchError> {
-		ASSETS.with(|d| -> DispatchResult {
-			let i = usize::try_from(asset).map_err(|_| DispatchError::Other("Index out of range"))?;
-			let mut d = d.borrow_mut();
-			let a = d.get_mut(i).ok_or(DispatchError::Other("Index out of range"))?;
-
-			let x = a.balances.get_mut(dest).ok_or(DispatchError::Other("Not found"))?;
-
-			*x = x.checked_sub(amount).ok_or(DispatchError::Other("Overflow"))?;
-
-			a.total = a.total.checked_sub(amount).ok_or(DispatchError::Other("Overflow"))?;
-
-			Ok(())
-		})?;
+		Self::ensure_exists(asset)?;
+		let balance = AssetBalances::get(asset, dest)
+			.ok_or(DispatchError::Other("Not found"))?
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Overflow"))?;
+		let total = AssetTotals::get(asset)
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Overflow"))?;
+		AssetBalances::insert(asset, dest, balance);
+		AssetTotals::insert(asset, total);
 		Ok(amount)
 	}
 
@@ -171,19 +172,11 @@ impl Inspect<AccountId> for TestAssets {
 	type AssetId = AssetId;
 	type Balance = Balance;
 	fn balance(asset: AssetId, who: &AccountId) -> Balance {
-		ASSETS
-			.with(|d| -> Option<Balance> {
-				let i = usize::try_from(asset).ok()?;
-				let d = d.borrow();
-				let a = d.get(i)?;
-				a.balances.get(who).copied()
-			})
-			.map(|x| x - 1)
-			.unwrap_or(0)
+		AssetBalances::get(asset, who).map(|x| x - 1).unwrap_or(0)
 	}
 
-	fn total_issuance(_asset: AssetId) -> Balance {
-		todo!()
+	fn total_issuance(asset: AssetId) -> Balance {
+		AssetTotals::get(asset)
 	}
 
 	fn minimum_balance(_asset: AssetId) -> Balance {
@@ -230,12 +223,6 @@ impl Unbalanced<AccountId> for TestAssets {
 	}
 }
 
-pub struct EmptyUnbalanceHandler;
-
-type Imbalance = <pallet_balances::Pallet<Test> as Currency<AccountId>>::NegativeImbalance;
-
-impl OnUnbalanced<Imbalance> for EmptyUnbalanceHandler {}
-
 pub struct EnsureStableAsset;
 impl EnsureOrigin<RuntimeOrigin> for EnsureStableAsset {
 	type Success = AccountId;
@@ -261,6 +248,29 @@ impl crate::traits::ValidateAssetId<i64> for EnsurePoolAssetId {
 	}
 }
 
+pub const RATE_PRECISION: AtLeast64BitUnsigned = 1_000_000_000_000_000_000;
+
+pub struct TestRateProvider;
+impl crate::traits::RateProvider<AssetId, AtLeast64BitUnsigned> for TestRateProvider {
+	fn get_rate(asset: AssetId) -> Option<AtLeast64BitUnsigned> {
+		RATES.with(|d| d.borrow().get(&asset).copied())
+	}
+}
+
+pub fn set_rate(asset: AssetId, rate: AtLeast64BitUnsigned) {
+	RATES.with(|d| d.borrow_mut().insert(asset, rate));
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct StableAssetBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper<AssetId> for StableAssetBenchmarkHelper {
+	// Every call creates a fresh asset, so the ids a benchmark asks for need not be known up front.
+	fn asset_id(_id: u32) -> AssetId {
+		TestAssets::create_asset().expect("asset should be created")
+	}
+}
+
 parameter_types! {
 	pub const StableAssetPalletId: PalletId = PalletId(*b"nuts/sta");
 }
@@ -276,10 +286,26 @@ impl stable_asset::Config for Test {
 	type FeePrecision = ConstU128<10_000_000_000>;
 	type APrecision = ConstU128<100>;
 	type PoolAssetLimit = ConstU32<5>;
+	type MaxA = ConstU128<1_000_000>;
+	type MinRampBlocks = ConstU64<10>;
+	type MaxAChangeFactor = ConstU128<100>;
+	type MaxRouteLength = ConstU32<3>;
+	type MaxOracleObservations = ConstU32<10>;
+	type PoolDustThreshold = ConstU128<1_000_000>;
+	type AssetDustThreshold = ConstU128<1_000_000_000_000_000>;
+	type DustAccount = ConstU128<100>;
+	type MaxMintFee = ConstU128<1_000_000_000>;
+	type MaxSwapFee = ConstU128<1_000_000_000>;
+	type MaxRedeemFee = ConstU128<1_000_000_000>;
 	type SwapExactOverAmount = ConstU128<100>;
 	type WeightInfo = ();
 	type ListingOrigin = EnsureStableAsset;
+	type PauseOrigin = EnsureStableAsset;
 	type EnsurePoolAssetId = EnsurePoolAssetId;
+	type RateProvider = TestRateProvider;
+	type RatePrecision = ConstU128<RATE_PRECISION>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = StableAssetBenchmarkHelper;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -16,15 +16,21 @@
 // limitations under the License.
 
 use crate::{
-	mock::*, Error, MintResult, RedeemMultiResult, RedeemProportionResult, RedeemSingleResult, StableAssetPoolInfo,
-	SwapResult,
+	mock::*, Error, GenesisPool, MintResult, PoolOperation, PoolRestrictions, PoolStatus, RedeemMultiResult,
+	RedeemProportionResult, RedeemSingleResult, StableAssetPoolInfo, SwapResult, SwapRouteStep,
 };
 use frame_support::traits::fungibles::{Inspect, Mutate};
-use frame_support::{assert_noop, assert_ok};
+use frame_support::traits::tokens::{Fortitude, Precision, Preservation};
+use frame_support::traits::BuildGenesisConfig;
+use frame_support::{assert_err, assert_noop, assert_ok, dispatch::GetDispatchInfo, weights::Weight};
 use sp_runtime::DispatchError;
 
 pub const BALANCE_OFF: u128 = 1;
 
+fn newton_iterations_weight(asset_length: u32, iterations: u32) -> Weight {
+	<() as crate::WeightInfo>::newton_iteration(asset_length).saturating_mul(iterations.into())
+}
+
 fn last_event() -> RuntimeEvent {
 	frame_system::pallet::Pallet::<Test>::events()
 		.pop()
@@ -32,7 +38,7 @@ fn last_event() -> RuntimeEvent {
 		.event
 }
 
-fn create_pool() -> (i64, i64, i64, u64) {
+fn create_pool() -> (i64, i64, i64, u128) {
 	let coin0 = TestAssets::create_asset().expect("asset should be created");
 	let coin1 = TestAssets::create_asset().expect("asset should be created");
 	let pool_asset = TestAssets::create_asset().expect("asset should be created");
@@ -52,7 +58,7 @@ fn create_pool() -> (i64, i64, i64, u64) {
 		1,
 		1000000000000000000u128,
 	));
-	(coin0, coin1, pool_asset, 8319403528785522541u64)
+	(coin0, coin1, pool_asset, 30160825295207673652903702381u128)
 }
 
 #[test]
@@ -76,19 +82,22 @@ fn create_pool_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset: 1,
-				assets: vec![1, 2],
-				precisions: vec![1u128, 1u128],
+				assets: vec![1, 2].try_into().unwrap(),
+				precisions: vec![1u128, 1u128].try_into().unwrap(),
 				mint_fee: 1u128,
 				swap_fee: 1u128,
 				redeem_fee: 1u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 0u128,
+				pool_token_supply: 0u128,
 				a: 1u128,
 				a_block: 0,
 				future_a: 1u128,
 				future_a_block: 0,
-				balances: vec![0, 0],
+				balances: vec![0, 0].try_into().unwrap(),
 				fee_recipient: 1,
-				account_id: 8319403528785522541u64,
+				account_id: 30160825295207673652903702381u128,
 				yield_recipient: 1,
 				precision: 1000000000000000000u128,
 			})
@@ -150,17 +159,20 @@ fn modify_a_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 0u128,
+				pool_token_supply: 0u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 100u128,
 				future_a_block: 100,
-				balances: vec![0, 0],
+				balances: vec![0, 0].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -171,14 +183,58 @@ fn modify_a_successful() {
 }
 
 #[test]
-fn modify_a_argument_error_failed() {
+fn modify_a_ramp_too_short_failed() {
 	new_test_ext().execute_with(|| {
 		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(20);
 
 		assert_noop!(
 			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 100, 0),
-			Error::<Test>::ArgumentsError
+			Error::<Test>::RampTooShort
+		);
+		assert_noop!(
+			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 100, 29),
+			Error::<Test>::RampTooShort
+		);
+		assert_ok!(StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 100, 30));
+	});
+}
+
+#[test]
+fn modify_a_over_max_failed() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+
+		assert_noop!(
+			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 1_000_001, 100),
+			Error::<Test>::AOverMax
+		);
+		assert_ok!(StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 1_000_000, 100));
+	});
+}
+
+#[test]
+fn modify_a_change_too_large_failed() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+
+		assert_noop!(
+			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 99, 100),
+			Error::<Test>::AChangeTooLarge
+		);
+		assert_noop!(
+			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 0, 100),
+			Error::<Test>::AChangeTooLarge
+		);
+
+		// the factor applies to the A at the current block of a ramp in progress
+		assert_ok!(StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 1_000_000, 100));
+		System::set_block_number(50);
+		assert_noop!(
+			StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 5049, 200),
+			Error::<Test>::AChangeTooLarge
 		);
+		assert_ok!(StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 5050, 200));
 	});
 }
 
@@ -194,6 +250,48 @@ fn modify_a_pool_not_found() {
 	});
 }
 
+#[test]
+fn stop_ramp_a_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+
+		assert_ok!(StableAsset::modify_a(RuntimeOrigin::signed(1), 0, 100, 100));
+		System::set_block_number(50);
+		assert_ok!(StableAsset::stop_ramp_a(RuntimeOrigin::signed(1), 0));
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(pool_info.a, 5050u128);
+		assert_eq!(pool_info.a_block, 50);
+		assert_eq!(pool_info.future_a, 5050u128);
+		assert_eq!(pool_info.future_a_block, 50);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::RampStopped {
+				pool_id: 0,
+				a: 5050u128,
+				time: 50,
+			})
+		);
+
+		System::set_block_number(100);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_current_a(0),
+			Some(5050u128)
+		);
+	});
+}
+
+#[test]
+fn stop_ramp_a_pool_not_found() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+
+		assert_noop!(
+			StableAsset::stop_ramp_a(RuntimeOrigin::signed(1), 1),
+			Error::<Test>::PoolNotFound
+		);
+	});
+}
+
 #[test]
 fn mint_successful_equal_amounts() {
 	new_test_ext().execute_with(|| {
@@ -205,17 +303,20 @@ fn mint_successful_equal_amounts() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 199999980000000000u128,
+				pool_token_supply: 200000000000000000u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![99999990000000000u128, 99999990000000000u128],
+				balances: vec![99999990000000000u128, 99999990000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -247,17 +348,20 @@ fn mint_successful_different_amounts() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 299906783104508635u128,
+				pool_token_supply: 299906803112262055u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![99999990000000000u128, 199999990000000000u128],
+				balances: vec![99999990000000000u128, 199999990000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -284,6 +388,7 @@ fn mint_successful_different_amounts() {
 			balances: _,
 			total_supply: _,
 			min_output_amount: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(mint_amount, 299606896309149793u128);
@@ -376,17 +481,20 @@ fn swap_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 300006969999594867u128,
+				pool_token_supply: 300006990014032079u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![149999990000000000u128, 150006980000000000u128],
+				balances: vec![149999990000000000u128, 150006980000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -408,6 +516,8 @@ fn swap_successful() {
 			balances: _,
 			total_supply: _,
 			min_output_amount: _,
+			fee_amount: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(dx, 5000000u128);
@@ -418,6 +528,165 @@ fn swap_successful() {
 	});
 }
 
+#[test]
+fn swap_with_rate_successful() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, _pool_asset, swap_id) = create_pool();
+		System::set_block_number(2);
+		// one coin0 is worth two coin1
+		set_rate(coin0, 2 * RATE_PRECISION);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(
+			pool_info.balances.to_vec(),
+			vec![199999980000000000u128, 199999990000000000u128]
+		);
+
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 1000000u128, 0, 2));
+		assert_eq!(TestAssets::balance(coin0, &swap_id), 11000000u128 - BALANCE_OFF);
+		assert_eq!(TestAssets::balance(coin1, &swap_id), 18005002u128 - BALANCE_OFF);
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::TokenSwapped {
+			input_amount: dx,
+			output_amount: dy,
+			..
+		}) = last_event()
+		{
+			assert_eq!(dx, 1000000u128);
+			assert_eq!(dy, 1994998u128);
+		} else {
+			panic!("Unexpected event");
+		}
+	});
+}
+
+#[test]
+fn rate_increase_accrues_to_liquidity_providers() {
+	new_test_ext().execute_with(|| {
+		let (coin0, _coin1, pool_asset, swap_id) = create_pool();
+		System::set_block_number(2);
+		set_rate(coin0, RATE_PRECISION);
+
+		let amounts = vec![10000000u128, 10000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let old_pool_info = StableAsset::pools(0).unwrap();
+		let old_virtual_price = StableAsset::get_pool_virtual_price(&old_pool_info, RATE_PRECISION).unwrap();
+
+		// the 10% increase of the value of coin0 raises the value of every pool token
+		set_rate(coin0, RATE_PRECISION / 10 * 11);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![1000000u128, 0u128],
+			0
+		));
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(pool_info.balances[0], 120999989000000000u128);
+		let (mint_amount, fee_amount) = match last_event() {
+			RuntimeEvent::StableAsset(crate::pallet::Event::Minted {
+				output_amount,
+				fee_amount,
+				..
+			}) => (output_amount, fee_amount),
+			_ => panic!("Unexpected event"),
+		};
+		// no pool token is minted for the gain
+		assert_eq!(
+			pool_info.pool_token_supply,
+			old_pool_info.pool_token_supply + mint_amount + fee_amount
+		);
+		assert!(!System::events().into_iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::StableAsset(crate::pallet::Event::YieldCollected { .. })
+		)));
+		let virtual_price = StableAsset::get_pool_virtual_price(&pool_info, RATE_PRECISION).unwrap();
+		assert!(virtual_price > old_virtual_price * 104 / 100);
+		assert_eq!(TestAssets::total_issuance(pool_asset), pool_info.pool_token_supply);
+		assert_eq!(
+			crate::PoolRates::<Test>::get(0).unwrap().to_vec(),
+			vec![RATE_PRECISION / 10 * 11, RATE_PRECISION]
+		);
+		assert_ok!(StableAsset::do_try_state());
+
+		// along with a rate increase, only the D gained by a rebasing balance is collected as yield
+		System::reset_events();
+		set_rate(coin0, RATE_PRECISION / 10 * 12);
+		assert_ok!(TestAssets::mint_into(coin0, &swap_id, 1000000u128));
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![0u128, 1000000u128],
+			0
+		));
+		let yield_amount = System::events()
+			.into_iter()
+			.find_map(|record| match record.event {
+				RuntimeEvent::StableAsset(crate::pallet::Event::YieldCollected { who: 1, amount, .. }) => Some(amount),
+				_ => None,
+			})
+			.unwrap();
+		let pool_info = StableAsset::pools(0).unwrap();
+		let virtual_price = StableAsset::get_pool_virtual_price(&pool_info, RATE_PRECISION).unwrap();
+		// the rebased 1000000 coin0 are worth 1.2 each, less the rounding of get_d
+		let rebased_value = 12000000000000000u128;
+		assert!(yield_amount * virtual_price / RATE_PRECISION <= rebased_value);
+		assert!(yield_amount * virtual_price / RATE_PRECISION > rebased_value / 100 * 99);
+		assert_eq!(TestAssets::total_issuance(pool_asset), pool_info.pool_token_supply);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn rate_decrease_shared_by_liquidity_providers() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		set_rate(coin0, RATE_PRECISION);
+
+		let amounts = vec![10000000u128, 10000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let old_pool_info = StableAsset::pools(0).unwrap();
+
+		// the 1% drop of the value of coin0 lowers the value of every pool token
+		set_rate(coin0, RATE_PRECISION / 100 * 99);
+		let coin0_balance = TestAssets::balance(coin0, &1);
+		let coin1_balance = TestAssets::balance(coin1, &1);
+		let pool_token_balance = TestAssets::balance(pool_asset, &1);
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			pool_token_balance / 2,
+			vec![0u128, 0u128]
+		));
+		assert!(System::events().into_iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::StableAsset(crate::pallet::Event::LossShared {
+				old_total_supply,
+				new_total_supply,
+				virtual_price,
+				..
+			}) if old_total_supply == old_pool_info.total_supply
+				&& new_total_supply < old_total_supply
+				&& virtual_price < 1000000000000000000u128
+		)));
+		assert!(TestAssets::balance(coin0, &1) > coin0_balance);
+		assert!(TestAssets::balance(coin1, &1) > coin1_balance);
+		let pool_info = StableAsset::pools(0).unwrap();
+		// no pool token is minted or burnt for the loss
+		assert_eq!(TestAssets::total_issuance(pool_asset), pool_info.pool_token_supply);
+		assert!(pool_info.pool_token_supply > pool_info.total_supply);
+		assert_ok!(StableAsset::do_try_state());
+
+		// a zero rate is rejected
+		set_rate(coin0, 0);
+		assert_noop!(
+			StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 1000000u128, 0, 2),
+			Error::<Test>::InvalidRate
+		);
+	});
+}
+
 #[test]
 fn swap_failed_same_token() {
 	new_test_ext().execute_with(|| {
@@ -541,32 +810,35 @@ fn redeem_proportion_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
-				total_supply: 200406793007994387u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
+				total_supply: 200406803032820914u128,
+				pool_token_supply: 200406816402608209u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![66823020000000000u128, 133646050000000000u128],
+				balances: vec![66823030000000000u128, 133646050000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
 				precision: 1000000000000000000u128,
 			})
 		);
-		assert_eq!(TestAssets::balance(coin0, &1), 93317697u128 - BALANCE_OFF);
+		assert_eq!(TestAssets::balance(coin0, &1), 93317696u128 - BALANCE_OFF);
 		assert_eq!(TestAssets::balance(coin1, &1), 86635394u128 - BALANCE_OFF);
-		assert_eq!(TestAssets::balance(coin0, &swap_id), 6682303u128 - BALANCE_OFF);
+		assert_eq!(TestAssets::balance(coin0, &swap_id), 6682304u128 - BALANCE_OFF);
 		assert_eq!(TestAssets::balance(coin1, &swap_id), 13364606u128 - BALANCE_OFF);
 		assert_eq!(
 			TestAssets::balance(pool_asset, &1),
 			199606896309149793u128 - BALANCE_OFF
 		);
-		assert_eq!(TestAssets::balance(pool_asset, &2), 799916706598014u128 - BALANCE_OFF);
+		assert_eq!(TestAssets::balance(pool_asset, &2), 799920093458416u128 - BALANCE_OFF);
 		if let RuntimeEvent::StableAsset(crate::pallet::Event::RedeemedProportion {
 			redeemer: _,
 			pool_id: _,
@@ -577,10 +849,11 @@ fn redeem_proportion_successful() {
 			balances: _,
 			total_supply: _,
 			min_output_amounts: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(amount, 100000000000000000u128);
-			assert_eq!(amounts, vec![3317697u128, 6635394u128]);
+			assert_eq!(amounts, vec![3317696u128, 6635394u128]);
 			assert_eq!(fee_amount, 500000000000000u128);
 		} else {
 			panic!("Unexpected event");
@@ -698,17 +971,20 @@ fn redeem_single_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 200406798920642765u128,
+				pool_token_supply: 200406812290429786u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![4968380000000000u128, 199999990000000000u128],
+				balances: vec![4968380000000000u128, 199999990000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -723,7 +999,7 @@ fn redeem_single_successful() {
 			TestAssets::balance(pool_asset, &1),
 			199606896309149793u128 - BALANCE_OFF
 		);
-		assert_eq!(TestAssets::balance(pool_asset, &2), 799922619246391u128);
+		assert_eq!(TestAssets::balance(pool_asset, &2), 799915981279992u128);
 		if let RuntimeEvent::StableAsset(crate::pallet::Event::RedeemedSingle {
 			redeemer: _,
 			pool_id: _,
@@ -735,6 +1011,7 @@ fn redeem_single_successful() {
 			total_supply: _,
 			min_output_amount: _,
 			output_asset: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(input_amount, 100000000000000000u128);
@@ -846,17 +1123,20 @@ fn redeem_multi_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 10000000u128,
 				swap_fee: 20000000u128,
 				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 199834552642910514u128,
+				pool_token_supply: 199834565974521132u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![49999990000000000u128, 149999990000000000u128],
+				balances: vec![49999990000000000u128, 149999990000000000u128].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -867,8 +1147,8 @@ fn redeem_multi_successful() {
 		assert_eq!(TestAssets::balance(coin1, &1), 85000000u128 - BALANCE_OFF);
 		assert_eq!(TestAssets::balance(coin0, &swap_id), 5000000u128 - BALANCE_OFF);
 		assert_eq!(TestAssets::balance(coin1, &swap_id), 15000000u128 - BALANCE_OFF);
-		assert_eq!(TestAssets::balance(pool_asset, &1), 199031790317593892u128);
-		assert_eq!(TestAssets::balance(pool_asset, &2), 802782333070040u128);
+		assert_eq!(TestAssets::balance(pool_asset, &1), 199031783607902634u128);
+		assert_eq!(TestAssets::balance(pool_asset, &2), 802782366618496u128);
 		if let RuntimeEvent::StableAsset(crate::pallet::Event::RedeemedMulti {
 			redeemer: _,
 			pool_id: _,
@@ -879,11 +1159,12 @@ fn redeem_multi_successful() {
 			balances: _,
 			total_supply: _,
 			max_input_amount: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
-			assert_eq!(amount, 100575105991555900u128);
+			assert_eq!(amount, 100575112701247158u128);
 			assert_eq!(amounts, vec![5000000u128, 5000000u128]);
-			assert_eq!(fee_amount, 502875529957779u128);
+			assert_eq!(fee_amount, 502875563506235u128);
 		} else {
 			panic!("Unexpected event");
 		}
@@ -954,7 +1235,7 @@ fn swap_exact_success() {
 
 		let result = StableAsset::get_swap_amount_exact(&pool_info, 0, 1, amount).unwrap();
 		let result_two = StableAsset::get_swap_amount(&pool_info, 0, 1, result.dx).unwrap();
-		assert_eq!(result_two.dy >= amount, true);
+		assert!(result_two.dy >= amount);
 	});
 }
 
@@ -990,7 +1271,91 @@ fn swap_exact_success_different_precision() {
 
 		let result = StableAsset::get_swap_amount_exact(&pool_info, 0, 1, amount).unwrap();
 		let result_two = StableAsset::get_swap_amount(&pool_info, 0, 1, result.dx).unwrap();
-		assert_eq!(result_two.dy >= amount, true);
+		assert!(result_two.dy >= amount);
+	});
+}
+
+#[test]
+fn swap_exact_output_successful() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, _pool_asset, swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 10000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+
+		let amount = 1000345u128;
+		let pool_info = StableAsset::pools(0).unwrap();
+		let result = StableAsset::get_swap_amount_exact(&pool_info, 0, 1, amount).unwrap();
+		let coin0_balance = TestAssets::balance(coin0, &1);
+		let coin1_balance = TestAssets::balance(coin1, &1);
+
+		assert_ok!(StableAsset::swap_exact_output(
+			RuntimeOrigin::signed(1),
+			0,
+			0,
+			1,
+			amount,
+			result.dx,
+			2
+		));
+		assert_eq!(TestAssets::balance(coin0, &1), coin0_balance - result.dx);
+		assert_eq!(TestAssets::balance(coin1, &1), coin1_balance + amount);
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(
+			pool_info.balances,
+			vec![
+				TestAssets::balance(coin0, &swap_id) * 10000000000u128,
+				TestAssets::balance(coin1, &swap_id) * 10000000000u128
+			]
+		);
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::TokenSwapped {
+			input_amount: dx,
+			output_amount: dy,
+			..
+		}) = last_event()
+		{
+			assert_eq!(dx, result.dx);
+			assert_eq!(dy, amount);
+		} else {
+			panic!("Unexpected event");
+		}
+	});
+}
+
+#[test]
+fn swap_exact_output_failed() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 10000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+
+		let amount = 1000345u128;
+		let pool_info = StableAsset::pools(0).unwrap();
+		let result = StableAsset::get_swap_amount_exact(&pool_info, 0, 1, amount).unwrap();
+
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 0, 0, 1, amount, result.dx - 1, 2),
+			Error::<Test>::SwapOverMax
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 0, 0, 0, amount, result.dx, 2),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 0, 0, 2, amount, result.dx, 2),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 0, 0, 1, amount, result.dx, 3),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 1, 0, 1, amount, result.dx, 2),
+			Error::<Test>::PoolNotFound
+		);
 	});
 }
 
@@ -1010,17 +1375,20 @@ fn modify_fees_successful() {
 			StableAsset::pools(0),
 			Some(StableAssetPoolInfo {
 				pool_asset,
-				assets: vec![coin0, coin1],
-				precisions: vec![10000000000u128, 10000000000u128],
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
 				mint_fee: 100u128,
 				swap_fee: 200u128,
 				redeem_fee: 300u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
 				total_supply: 0u128,
+				pool_token_supply: 0u128,
 				a: 10000u128,
 				a_block: 0,
 				future_a: 10000u128,
 				future_a_block: 0,
-				balances: vec![0, 0],
+				balances: vec![0, 0].try_into().unwrap(),
 				fee_recipient: 2,
 				account_id: swap_id,
 				yield_recipient: 1,
@@ -1030,6 +1398,83 @@ fn modify_fees_successful() {
 	});
 }
 
+#[test]
+fn modify_fees_too_high_failed() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+
+		assert_noop!(
+			StableAsset::modify_fees(RuntimeOrigin::signed(1), 0, Some(1000000001), None, None),
+			Error::<Test>::MintFeeTooHigh
+		);
+		assert_noop!(
+			StableAsset::modify_fees(RuntimeOrigin::signed(1), 0, None, Some(1000000001), None),
+			Error::<Test>::SwapFeeTooHigh
+		);
+		assert_noop!(
+			StableAsset::modify_fees(RuntimeOrigin::signed(1), 0, None, None, Some(10000000000)),
+			Error::<Test>::RedeemFeeTooHigh
+		);
+		assert_ok!(StableAsset::modify_fees(
+			RuntimeOrigin::signed(1),
+			0,
+			Some(1000000000),
+			Some(1000000000),
+			Some(1000000000)
+		));
+	});
+}
+
+#[test]
+fn create_pool_fee_too_high_failed() {
+	new_test_ext().execute_with(|| {
+		let create = |mint_fee: u128, swap_fee: u128, redeem_fee: u128| {
+			StableAsset::create_pool(
+				RuntimeOrigin::signed(1),
+				1,
+				vec![1, 2],
+				vec![1u128, 1u128],
+				mint_fee,
+				swap_fee,
+				redeem_fee,
+				1u128,
+				1,
+				1,
+				1000000000000000000u128,
+			)
+		};
+
+		assert_noop!(create(1000000001, 1, 1), Error::<Test>::MintFeeTooHigh);
+		assert_noop!(create(1, 10000000000, 1), Error::<Test>::SwapFeeTooHigh);
+		assert_noop!(create(1, 1, 1000000001), Error::<Test>::RedeemFeeTooHigh);
+		assert_ok!(create(1000000000, 1000000000, 1000000000));
+	});
+}
+
+#[test]
+fn create_pool_a_over_max_failed() {
+	new_test_ext().execute_with(|| {
+		let create = |initial_a: u128| {
+			StableAsset::create_pool(
+				RuntimeOrigin::signed(1),
+				1,
+				vec![1, 2],
+				vec![1u128, 1u128],
+				1u128,
+				1u128,
+				1u128,
+				initial_a,
+				1,
+				1,
+				1000000000000000000u128,
+			)
+		};
+
+		assert_noop!(create(1000001), Error::<Test>::AOverMax);
+		assert_ok!(create(1000000));
+	});
+}
+
 #[test]
 fn get_mint_amount_same_as_mint() {
 	new_test_ext().execute_with(|| {
@@ -1050,8 +1495,9 @@ fn get_mint_amount_same_as_mint() {
 		assert_eq!(
 			StableAsset::get_mint_amount(&pool_info, &amounts).unwrap(),
 			MintResult {
-				mint_amount: 91642963023874214130u128,
-				fee_amount: 91734697721595809u128,
+				mint_amount: 91642969137673268220u128,
+				fee_amount: 91734703841514782u128,
+				lp_fee_amount: 0u128,
 				balances: vec![1000199999990000000000u128, 399999990000000000u128],
 				total_supply: 623501681289305978589u128,
 			}
@@ -1068,11 +1514,12 @@ fn get_mint_amount_same_as_mint() {
 			total_supply,
 			fee_amount,
 			output_amount,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(total_supply, 623501681289305978589u128);
-			assert_eq!(fee_amount, 91734697721595809u128);
-			assert_eq!(output_amount, 91642963023874214130u128);
+			assert_eq!(fee_amount, 91734703841514782u128);
+			assert_eq!(output_amount, 91642969137673268220u128);
 		} else {
 			panic!("Unexpected event");
 		}
@@ -1102,7 +1549,9 @@ fn get_swap_amount_same_as_swap() {
 				dx: 5000000u128,
 				dy: 3120u128,
 				y: 199968725309184067u128,
-				balance_i: 1000149999990000000000u128
+				balance_i: 1000149999990000000000u128,
+				fee_amount: 6u128,
+				lp_fee_amount: 0u128,
 			}
 		);
 
@@ -1118,6 +1567,8 @@ fn get_swap_amount_same_as_swap() {
 			balances: _,
 			total_supply: _,
 			min_output_amount: _,
+			fee_amount: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(dx, 5000000u128);
@@ -1151,7 +1602,9 @@ fn get_swap_amount_exact_same_as_swap() {
 				dx: 4999349u128,
 				dy: 3120u128,
 				y: 1000149992487303920870u128,
-				balance_i: 1000099999990000000000u128
+				balance_i: 1000099999990000000000u128,
+				fee_amount: 6u128,
+				lp_fee_amount: 0u128,
 			}
 		);
 
@@ -1167,6 +1620,8 @@ fn get_swap_amount_exact_same_as_swap() {
 			balances: _,
 			total_supply: _,
 			min_output_amount: _,
+			fee_amount: _,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(dx, 4999349u128);
@@ -1197,10 +1652,11 @@ fn get_redeem_proportion_amount_same_as_redeem_proportion() {
 		assert_eq!(
 			StableAsset::get_redeem_proportion_amount(&pool_info, 100000000000000000u128).unwrap(),
 			RedeemProportionResult {
-				amounts: vec![18713074u128, 3742u128],
-				balances: vec![999912869248389498442u128, 199962567595789254u128],
+				amounts: vec![18713072u128, 3742u128],
+				balances: vec![999912869260873595824u128, 199962567598285824u128],
 				fee_amount: 500000000000000u128,
-				total_supply: 531667483567710168650u128,
+				lp_fee_amount: 0u128,
+				total_supply: 531667483574348135661u128,
 				redeem_amount: 99500000000000000u128,
 			}
 		);
@@ -1221,11 +1677,12 @@ fn get_redeem_proportion_amount_same_as_redeem_proportion() {
 			total_supply,
 			fee_amount,
 			output_amounts,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
-			assert_eq!(total_supply, 531667485117219048370u128);
+			assert_eq!(total_supply, 531667485125276670158u128);
 			assert_eq!(fee_amount, 500000000000000u128);
-			assert_eq!(output_amounts, vec![18713074u128, 3742u128]);
+			assert_eq!(output_amounts, vec![18713072u128, 3742u128]);
 		} else {
 			panic!("Unexpected event");
 		}
@@ -1252,10 +1709,11 @@ fn get_redeem_single_amount_same_as_redeem_single() {
 		assert_eq!(
 			StableAsset::get_redeem_single_amount(&pool_info, 100000000000000000u128, 0).unwrap(),
 			RedeemSingleResult {
-				dy: 24696237u128,
+				dy: 24696236u128,
 				fee_amount: 500000000000000u128,
-				total_supply: 531667483567710168650u128,
-				balances: vec![999853037611795513052u128, 199999990000000000u128],
+				lp_fee_amount: 0u128,
+				total_supply: 531667483574348135661u128,
+				balances: vec![999853037628270588262u128, 199999990000000000u128],
 				redeem_amount: 99500000000000000u128,
 			}
 		);
@@ -1279,10 +1737,11 @@ fn get_redeem_single_amount_same_as_redeem_single() {
 			total_supply: _,
 			fee_amount,
 			output_amount,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
 			assert_eq!(fee_amount, 500000000000000u128);
-			assert_eq!(output_amount, 24696237u128);
+			assert_eq!(output_amount, 24696236u128);
 		} else {
 			panic!("Unexpected event");
 		}
@@ -1307,13 +1766,14 @@ fn get_redeem_multi_amount_same_as_redeem_multi() {
 		);
 
 		assert_eq!(
-			StableAsset::get_redeem_multi_amount(&pool_info, &vec![5000000u128, 5000000u128]).unwrap(),
+			StableAsset::get_redeem_multi_amount(&pool_info, &[5000000u128, 5000000u128]).unwrap(),
 			RedeemMultiResult {
-				redeem_amount: 36679896644936924929u128,
-				fee_amount: 183399483224684624u128,
+				redeem_amount: 36679899091971700023u128,
+				fee_amount: 183399495459858500u128,
+				lp_fee_amount: 0u128,
 				balances: vec![1000049999990000000000u128, 149999990000000000u128],
 				total_supply: 495270486405997928345u128,
-				burn_amount: 36496497161712240305u128,
+				burn_amount: 36496499596511841523u128,
 			}
 		);
 
@@ -1333,10 +1793,11 @@ fn get_redeem_multi_amount_same_as_redeem_multi() {
 			total_supply: _,
 			fee_amount,
 			input_amount,
+			lp_fee_amount: _,
 		}) = last_event()
 		{
-			assert_eq!(fee_amount, 183399483224684624u128);
-			assert_eq!(input_amount, 36679896644936924929u128);
+			assert_eq!(fee_amount, 183399495459858500u128);
+			assert_eq!(input_amount, 36679899091971700023u128);
 		} else {
 			panic!("Unexpected event");
 		}
@@ -1436,3 +1897,2667 @@ fn swap_should_work_with_unbalance_coin1() {
 		));
 	});
 }
+
+#[test]
+fn pool_quotes_same_as_pool_info_quotes() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let pool_info = StableAsset::pools(0).unwrap();
+
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_mint_output_amount(0, vec![5000000u128, 5000000u128]),
+			StableAsset::get_mint_amount(&pool_info, &[5000000u128, 5000000u128]).ok()
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_redeem_proportion_output_amount(0, 100000000000000000u128),
+			StableAsset::get_redeem_proportion_amount(&pool_info, 100000000000000000u128).ok()
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_redeem_single_output_amount(0, 100000000000000000u128, 1),
+			StableAsset::get_redeem_single_amount(&pool_info, 100000000000000000u128, 1).ok()
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_redeem_multi_input_amount(
+				0,
+				vec![5000000u128, 5000000u128]
+			),
+			StableAsset::get_redeem_multi_amount(&pool_info, &[5000000u128, 5000000u128]).ok()
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_current_a(0),
+			Some(10000u128)
+		);
+
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_mint_output_amount(1, vec![5000000u128, 5000000u128]),
+			None
+		);
+		assert_eq!(<StableAsset as crate::traits::StableAsset>::get_current_a(1), None);
+	});
+}
+
+fn create_pool_with_liquidity(assets: Vec<i64>, precisions: Vec<u128>, amounts: Vec<u128>) -> (u32, i64) {
+	let pool_id = StableAsset::pool_count();
+	let pool_asset = TestAssets::create_asset().expect("asset should be created");
+	for (asset, amount) in assets.iter().zip(amounts.iter()) {
+		assert_ok!(TestAssets::mint_into(*asset, &1, *amount));
+	}
+	assert_ok!(StableAsset::create_pool(
+		RuntimeOrigin::signed(1),
+		pool_asset,
+		assets,
+		precisions,
+		10000000u128,
+		20000000u128,
+		50000000u128,
+		10000u128,
+		2,
+		1,
+		1000000000000000000u128,
+	));
+	assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), pool_id, amounts, 0));
+	(pool_id, pool_asset)
+}
+
+#[test]
+fn swap_route_across_pools_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		create_pool_with_liquidity(
+			vec![coin1, coin2],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+
+		let route = vec![
+			SwapRouteStep::Swap {
+				pool_id: 0,
+				input_index: 0,
+				output_index: 1,
+			},
+			SwapRouteStep::Swap {
+				pool_id: 1,
+				input_index: 0,
+				output_index: 1,
+			},
+		];
+		let first = <StableAsset as crate::traits::StableAsset>::get_swap_output_amount(0, 0, 1, 100000u128).unwrap();
+		let second = <StableAsset as crate::traits::StableAsset>::get_swap_output_amount(1, 0, 1, first.dy).unwrap();
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_multi_hop_route(coin0, coin2, 100000u128),
+			Some((route.clone(), second.dy))
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_route(coin0, coin2, 100000u128),
+			None
+		);
+
+		TestAssets::mint_into(coin0, &2, 100000u128).unwrap();
+		assert_ok!(StableAsset::swap_route(
+			RuntimeOrigin::signed(2),
+			route.clone(),
+			100000u128,
+			second.dy
+		));
+		assert_eq!(TestAssets::balance(coin2, &2), second.dy - BALANCE_OFF);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::RouteSwapped {
+				swapper: 2,
+				route,
+				input_asset: coin0,
+				output_asset: coin2,
+				input_amount: 100000u128,
+				min_output_amount: second.dy,
+				output_amount: second.dy,
+			})
+		);
+	});
+}
+
+#[test]
+fn swap_route_through_pool_asset_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (_, pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		create_pool_with_liquidity(
+			vec![pool_asset, coin2],
+			vec![1u128, 10000000000u128],
+			vec![50000000000000000u128, 5000000u128],
+		);
+		System::set_block_number(2);
+
+		let route = vec![
+			SwapRouteStep::Mint {
+				pool_id: 0,
+				input_index: 0,
+			},
+			SwapRouteStep::Swap {
+				pool_id: 1,
+				input_index: 0,
+				output_index: 1,
+			},
+		];
+		let minted = <StableAsset as crate::traits::StableAsset>::get_mint_output_amount(0, vec![100000u128, 0])
+			.unwrap()
+			.mint_amount;
+		let swapped = <StableAsset as crate::traits::StableAsset>::get_swap_output_amount(1, 0, 1, minted).unwrap();
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_multi_hop_route(coin0, coin2, 100000u128),
+			Some((route.clone(), swapped.dy))
+		);
+
+		TestAssets::mint_into(coin0, &2, 100000u128).unwrap();
+		assert_ok!(StableAsset::swap_route(RuntimeOrigin::signed(2), route, 100000u128, 0));
+		assert_eq!(TestAssets::balance(coin2, &2), swapped.dy - BALANCE_OFF);
+
+		// and back again through a single asset redeem
+		let route = vec![
+			SwapRouteStep::Swap {
+				pool_id: 1,
+				input_index: 1,
+				output_index: 0,
+			},
+			SwapRouteStep::RedeemSingle {
+				pool_id: 0,
+				output_index: 1,
+			},
+		];
+		let (best_route, _) =
+			<StableAsset as crate::traits::StableAsset>::get_best_multi_hop_route(coin2, coin1, 10000u128).unwrap();
+		assert_eq!(best_route, route);
+		assert_ok!(StableAsset::swap_route(RuntimeOrigin::signed(2), route, 10000u128, 0));
+		assert!(TestAssets::balance(coin1, &2) > 0);
+	});
+}
+
+#[test]
+fn swap_route_failed() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		create_pool_with_liquidity(
+			vec![coin1, coin2],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+		TestAssets::mint_into(coin0, &2, 100000u128).unwrap();
+
+		let step = SwapRouteStep::Swap {
+			pool_id: 0,
+			input_index: 0,
+			output_index: 1,
+		};
+		assert_noop!(
+			StableAsset::swap_route(RuntimeOrigin::signed(2), vec![], 100000u128, 0),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			StableAsset::swap_route(RuntimeOrigin::signed(2), vec![step; 4], 100000u128, 0),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			StableAsset::swap_route(
+				RuntimeOrigin::signed(2),
+				vec![
+					step,
+					SwapRouteStep::Swap {
+						pool_id: 1,
+						input_index: 1,
+						output_index: 0,
+					}
+				],
+				100000u128,
+				0
+			),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			StableAsset::swap_route(
+				RuntimeOrigin::signed(2),
+				vec![SwapRouteStep::Swap {
+					pool_id: 0,
+					input_index: 0,
+					output_index: 2,
+				}],
+				100000u128,
+				0
+			),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			StableAsset::swap_route(
+				RuntimeOrigin::signed(2),
+				vec![SwapRouteStep::Swap {
+					pool_id: 2,
+					input_index: 0,
+					output_index: 1,
+				}],
+				100000u128,
+				0
+			),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::swap_route(RuntimeOrigin::signed(2), vec![step], 100000u128, 100000u128),
+			Error::<Test>::SwapUnderMin
+		);
+		// a pool can't be used twice in a route
+		assert_noop!(
+			StableAsset::swap_route(
+				RuntimeOrigin::signed(2),
+				vec![
+					step,
+					SwapRouteStep::Swap {
+						pool_id: 0,
+						input_index: 1,
+						output_index: 0,
+					}
+				],
+				100000u128,
+				0
+			),
+			Error::<Test>::InvalidRoute
+		);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_multi_hop_route(coin0, coin0, 100000u128),
+			None
+		);
+	});
+}
+
+/// Create a pool of `coin` and the pool token of `base_pool_asset`, without liquidity.
+fn create_pool_of_pool_token(coin: i64, base_pool_asset: i64) -> u32 {
+	let pool_id = StableAsset::pool_count();
+	let pool_asset = TestAssets::create_asset().expect("asset should be created");
+	assert_ok!(TestAssets::mint_into(coin, &1, 5000000u128));
+	assert_ok!(StableAsset::create_pool(
+		RuntimeOrigin::signed(1),
+		pool_asset,
+		vec![coin, base_pool_asset],
+		vec![10000000000u128, 1u128],
+		10000000u128,
+		20000000u128,
+		50000000u128,
+		10000u128,
+		2,
+		1,
+		1000000000000000000u128,
+	));
+	pool_id
+}
+
+/// Create a metapool of `coin` and the pool token of `base_pool_asset`, with the base pool tokens
+/// account 1 minted in the base pool.
+fn create_meta_pool_of(coin: i64, base_pool_asset: i64) -> u32 {
+	let pool_id = create_pool_of_pool_token(coin, base_pool_asset);
+	let base_pool_id = StableAsset::pool_asset_pool(base_pool_asset).unwrap();
+	assert_ok!(StableAsset::set_base_pool(
+		RuntimeOrigin::signed(1),
+		pool_id,
+		base_pool_id
+	));
+	assert_ok!(StableAsset::mint(
+		RuntimeOrigin::signed(1),
+		pool_id,
+		vec![5000000u128, 50000000000000000u128],
+		0
+	));
+	pool_id
+}
+
+fn create_meta_pool() -> (i64, i64, i64, i64) {
+	let coin0 = TestAssets::create_asset().expect("asset should be created");
+	let coin1 = TestAssets::create_asset().expect("asset should be created");
+	let coin2 = TestAssets::create_asset().expect("asset should be created");
+	let (_, base_pool_asset) = create_pool_with_liquidity(
+		vec![coin0, coin1],
+		vec![10000000000u128, 10000000000u128],
+		vec![10000000u128, 10000000u128],
+	);
+	create_meta_pool_of(coin2, base_pool_asset);
+	(coin0, coin1, coin2, base_pool_asset)
+}
+
+#[test]
+fn metapool_prices_base_pool_token_at_virtual_price() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (_, base_pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_eq!(StableAsset::pool_asset_pool(base_pool_asset), Some(0));
+		// the swap fees kept in the base pool raise its virtual price
+		assert_ok!(StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 0, 0u128));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 5000000u128));
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		let base_pool_info = StableAsset::pools(0).unwrap();
+		let virtual_price = StableAsset::get_pool_virtual_price(&base_pool_info, RATE_PRECISION).unwrap();
+		assert_eq!(
+			virtual_price,
+			base_pool_info.total_supply * RATE_PRECISION / base_pool_info.pool_token_supply
+		);
+		assert!(virtual_price > RATE_PRECISION);
+
+		create_meta_pool_of(coin2, base_pool_asset);
+		let pool_info = StableAsset::pools(1).unwrap();
+		assert_eq!(
+			pool_info.balances[1],
+			(50000000000000000u128 - BALANCE_OFF) * virtual_price / RATE_PRECISION
+		);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn pool_holding_pool_token_is_not_metapool_by_default() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (_, base_pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_ok!(StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 0, 0u128));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 5000000u128));
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		assert!(
+			StableAsset::get_pool_virtual_price(&StableAsset::pools(0).unwrap(), RATE_PRECISION).unwrap()
+				> RATE_PRECISION
+		);
+
+		// the base pool token is valued at one without `set_base_pool`
+		let pool_id = create_pool_of_pool_token(coin2, base_pool_asset);
+		assert_ok!(TestAssets::mint_into(coin2, &1, 5000000u128));
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![5000000u128, 50000000000000000u128],
+			0
+		));
+		assert_eq!(StableAsset::base_pool(pool_id), None);
+		let pool_info = StableAsset::pools(pool_id).unwrap();
+		assert_eq!(pool_info.balances[1], 50000000000000000u128 - BALANCE_OFF);
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(1), pool_id, 1, 0, 100000u128, 0),
+			Error::<Test>::NotMetaPool
+		);
+
+		// nor once it has liquidity
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, 0),
+			Error::<Test>::PoolNotEmpty
+		);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn set_base_pool_failed() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (_, base_pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		let pool_id = create_pool_of_pool_token(coin2, base_pool_asset);
+		let (other_pool_id, _) = create_pool_with_liquidity(
+			vec![coin1, coin2],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::none(), pool_id, 0),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), 5, 0),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, 5),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, pool_id),
+			Error::<Test>::ArgumentsError
+		);
+		// the pool does not hold the pool token of the other pool
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, other_pool_id),
+			Error::<Test>::ArgumentsError
+		);
+		System::set_block_number(2);
+		assert_ok!(StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, 0));
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::BasePoolSet {
+				pool_id,
+				base_pool_id: 0
+			})
+		);
+		assert_eq!(StableAsset::base_pool(pool_id), Some(0));
+		assert!(crate::MetaPools::<Test>::contains_key(0, pool_id));
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), pool_id, 0),
+			Error::<Test>::ArgumentsError
+		);
+
+		let second_pool_id = create_pool_of_pool_token(coin2, base_pool_asset);
+		assert_ok!(StableAsset::start_destroy_pool(RuntimeOrigin::signed(1), 0));
+		assert_noop!(
+			StableAsset::set_base_pool(RuntimeOrigin::signed(1), second_pool_id, 0),
+			Error::<Test>::PoolDestroying
+		);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn swap_underlying_successful() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, coin2, _base_pool_asset) = create_meta_pool();
+		System::set_block_number(2);
+
+		// from a base pool asset into the metapool asset
+		let minted = <StableAsset as crate::traits::StableAsset>::get_mint_output_amount(0, vec![100000u128, 0])
+			.unwrap()
+			.mint_amount;
+		let swapped = <StableAsset as crate::traits::StableAsset>::get_swap_output_amount(1, 1, 0, minted).unwrap();
+		TestAssets::mint_into(coin0, &2, 100000u128).unwrap();
+		assert_ok!(StableAsset::swap_underlying(
+			RuntimeOrigin::signed(2),
+			1,
+			1,
+			0,
+			100000u128,
+			swapped.dy
+		));
+		assert_eq!(TestAssets::balance(coin2, &2), swapped.dy - BALANCE_OFF);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::RouteSwapped {
+				swapper: 2,
+				route: vec![
+					SwapRouteStep::Mint {
+						pool_id: 0,
+						input_index: 0,
+					},
+					SwapRouteStep::Swap {
+						pool_id: 1,
+						input_index: 1,
+						output_index: 0,
+					},
+				],
+				input_asset: coin0,
+				output_asset: coin2,
+				input_amount: 100000u128,
+				min_output_amount: swapped.dy,
+				output_amount: swapped.dy,
+			})
+		);
+
+		// from the metapool asset into a base pool asset
+		assert_ok!(StableAsset::swap_underlying(
+			RuntimeOrigin::signed(2),
+			1,
+			0,
+			2,
+			10000u128,
+			0
+		));
+		assert!(TestAssets::balance(coin1, &2) > 0);
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::RouteSwapped { route, .. }) = last_event() {
+			assert_eq!(
+				route,
+				vec![
+					SwapRouteStep::Swap {
+						pool_id: 1,
+						input_index: 0,
+						output_index: 1,
+					},
+					SwapRouteStep::RedeemSingle {
+						pool_id: 0,
+						output_index: 1,
+					},
+				]
+			);
+		} else {
+			panic!("Unexpected event");
+		}
+
+		// between base pool assets
+		assert_ok!(StableAsset::swap_underlying(
+			RuntimeOrigin::signed(2),
+			1,
+			2,
+			1,
+			1000u128,
+			0
+		));
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::RouteSwapped { route, .. }) = last_event() {
+			assert_eq!(
+				route,
+				vec![SwapRouteStep::Swap {
+					pool_id: 0,
+					input_index: 1,
+					output_index: 0,
+				}]
+			);
+		} else {
+			panic!("Unexpected event");
+		}
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn swap_underlying_failed() {
+	new_test_ext().execute_with(|| {
+		let (coin0, _coin1, _coin2, _base_pool_asset) = create_meta_pool();
+		System::set_block_number(2);
+		TestAssets::mint_into(coin0, &2, 100000u128).unwrap();
+
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(2), 0, 0, 1, 100000u128, 0),
+			Error::<Test>::NotMetaPool
+		);
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(2), 2, 1, 0, 100000u128, 0),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(2), 1, 1, 1, 100000u128, 0),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(2), 1, 1, 3, 100000u128, 0),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::swap_underlying(RuntimeOrigin::signed(2), 1, 1, 0, 100000u128, 100000u128),
+			Error::<Test>::SwapUnderMin
+		);
+	});
+}
+
+#[test]
+fn create_pool_pool_asset_in_use() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, pool_asset, _swap_id) = create_pool();
+		assert_noop!(
+			StableAsset::create_pool(
+				RuntimeOrigin::signed(1),
+				pool_asset,
+				vec![1, 2],
+				vec![1u128, 1u128],
+				1u128,
+				1u128,
+				1u128,
+				1u128,
+				1,
+				1,
+				1000000000000000000u128,
+			),
+			Error::<Test>::PoolAssetInUse
+		);
+	});
+}
+
+fn assert_pool_operations_paused(pool_id: u32) {
+	assert_noop!(
+		StableAsset::mint(RuntimeOrigin::signed(1), pool_id, vec![10000000u128, 10000000u128], 0),
+		Error::<Test>::PoolPaused
+	);
+	assert_noop!(
+		StableAsset::swap(RuntimeOrigin::signed(1), pool_id, 0, 1, 5000000u128, 0, 2),
+		Error::<Test>::PoolPaused
+	);
+	assert_noop!(
+		StableAsset::swap_exact_output(RuntimeOrigin::signed(1), pool_id, 0, 1, 5000000u128, 10000000u128, 2),
+		Error::<Test>::PoolPaused
+	);
+	assert_noop!(
+		StableAsset::redeem_single(RuntimeOrigin::signed(1), pool_id, 100000000000000000u128, 0, 0u128, 2),
+		Error::<Test>::PoolPaused
+	);
+	assert_noop!(
+		StableAsset::redeem_multi(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![5000000u128, 5000000u128],
+			1100000000000000000u128
+		),
+		Error::<Test>::PoolPaused
+	);
+}
+
+#[test]
+fn pause_pool_successful() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		assert_ok!(StableAsset::set_pool_status(
+			RuntimeOrigin::signed(1),
+			0,
+			PoolStatus::Paused
+		));
+		assert_eq!(StableAsset::pool_status(0), PoolStatus::Paused);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::PoolStatusModified {
+				pool_id: 0,
+				status: PoolStatus::Paused,
+			})
+		);
+
+		assert_pool_operations_paused(0);
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_route(coin0, coin1, 5000000u128),
+			None
+		);
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			vec![0u128, 0u128]
+		));
+
+		assert_ok!(StableAsset::set_pool_status(
+			RuntimeOrigin::signed(1),
+			0,
+			PoolStatus::Active
+		));
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+	});
+}
+
+#[test]
+fn pause_all_pools_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		assert_ok!(StableAsset::set_paused(RuntimeOrigin::signed(1), true));
+		assert!(StableAsset::paused());
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::PausedModified { paused: true })
+		);
+
+		assert_pool_operations_paused(0);
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			vec![0u128, 0u128]
+		));
+
+		assert_ok!(StableAsset::set_paused(RuntimeOrigin::signed(1), false));
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+	});
+}
+
+#[test]
+fn pause_pool_failed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StableAsset::set_pool_status(RuntimeOrigin::signed(1), 0, PoolStatus::Paused),
+			Error::<Test>::PoolNotFound
+		);
+		create_pool();
+		assert_noop!(
+			StableAsset::set_pool_status(RuntimeOrigin::none(), 0, PoolStatus::Paused),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StableAsset::set_paused(RuntimeOrigin::none(), true),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn restrict_pool_operations_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let restrictions = PoolRestrictions::default()
+			.with_operation_disabled(PoolOperation::Swap)
+			.with_operation_disabled(PoolOperation::RedeemProportion);
+		assert_ok!(StableAsset::set_restrictions(RuntimeOrigin::signed(1), 0, restrictions));
+		assert_eq!(StableAsset::pool_restrictions(0), restrictions);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::RestrictionsModified {
+				pool_id: 0,
+				restrictions,
+			})
+		);
+
+		assert_noop!(
+			StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2),
+			Error::<Test>::OperationDisabled
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 10000000u128, 2),
+			Error::<Test>::OperationDisabled
+		);
+		assert_noop!(
+			StableAsset::redeem_proportion(RuntimeOrigin::signed(1), 0, 100000000000000000u128, vec![0u128, 0u128]),
+			Error::<Test>::OperationDisabled
+		);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128],
+			0
+		));
+		assert_ok!(StableAsset::redeem_single(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			0,
+			0u128,
+			2
+		));
+	});
+}
+
+#[test]
+fn restrict_pool_assets_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+
+		let amounts = vec![10000000u128, 20000000u128];
+		assert_ok!(StableAsset::mint(RuntimeOrigin::signed(1), 0, amounts, 0));
+		let restrictions = PoolRestrictions::default()
+			.with_input_disabled(1)
+			.with_output_disabled(0);
+		assert_ok!(StableAsset::set_restrictions(RuntimeOrigin::signed(1), 0, restrictions));
+
+		assert_noop!(
+			StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 5000000u128, 0, 2),
+			Error::<Test>::AssetDisabled
+		);
+		assert_noop!(
+			StableAsset::mint(RuntimeOrigin::signed(1), 0, vec![10000000u128, 10000000u128], 0),
+			Error::<Test>::AssetDisabled
+		);
+		assert_noop!(
+			StableAsset::redeem_single(RuntimeOrigin::signed(1), 0, 100000000000000000u128, 0, 0u128, 2),
+			Error::<Test>::AssetDisabled
+		);
+		assert_noop!(
+			StableAsset::redeem_multi(
+				RuntimeOrigin::signed(1),
+				0,
+				vec![5000000u128, 5000000u128],
+				1100000000000000000u128
+			),
+			Error::<Test>::AssetDisabled
+		);
+
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 0u128],
+			0
+		));
+		assert_ok!(StableAsset::redeem_single(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			1,
+			0u128,
+			2
+		));
+		assert_ok!(StableAsset::redeem_multi(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![0u128, 5000000u128],
+			1100000000000000000u128
+		));
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			vec![0u128, 0u128]
+		));
+	});
+}
+
+#[test]
+fn restrict_pool_failed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StableAsset::set_restrictions(RuntimeOrigin::signed(1), 0, PoolRestrictions::default()),
+			Error::<Test>::PoolNotFound
+		);
+		create_pool();
+		assert_noop!(
+			StableAsset::set_restrictions(RuntimeOrigin::none(), 0, PoolRestrictions::default()),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+fn old_pool_info(assets: Vec<i64>) -> crate::migrations::v1::OldStableAssetPoolInfo<i64, u128, u128, u128, u64> {
+	crate::migrations::v1::OldStableAssetPoolInfo {
+		pool_asset: 100,
+		precisions: vec![10000000000u128; assets.len()],
+		balances: vec![99999990000000000u128; assets.len()],
+		assets,
+		mint_fee: 10000000u128,
+		swap_fee: 20000000u128,
+		redeem_fee: 50000000u128,
+		total_supply: 199999980000000000u128,
+		a: 10000u128,
+		a_block: 0,
+		future_a: 10000u128,
+		future_a_block: 0,
+		fee_recipient: 2,
+		account_id: 30160825295207673652903702381u128,
+		yield_recipient: 1,
+		precision: 1000000000000000000u128,
+	}
+}
+
+#[test]
+fn migrate_to_v1_successful() {
+	use frame_support::storage::unhashed;
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+	use parity_scale_codec::Encode;
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+		unhashed::put(
+			&crate::Pools::<Test>::hashed_key_for(1),
+			&old_pool_info(vec![1, 2, 3, 4, 5, 6]),
+		);
+
+		crate::migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(StableAsset::on_chain_storage_version(), 1);
+		assert_eq!(
+			crate::migrations::v1::Pools::<Test>::get(0),
+			Some(crate::migrations::v1::StableAssetPoolInfoV1 {
+				pool_asset: 100,
+				assets: vec![1, 2].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
+				mint_fee: 10000000u128,
+				swap_fee: 20000000u128,
+				redeem_fee: 50000000u128,
+				total_supply: 199999980000000000u128,
+				a: 10000u128,
+				a_block: 0,
+				future_a: 10000u128,
+				future_a_block: 0,
+				balances: vec![99999990000000000u128, 99999990000000000u128].try_into().unwrap(),
+				fee_recipient: 2,
+				account_id: 30160825295207673652903702381u128,
+				yield_recipient: 1,
+				precision: 1000000000000000000u128,
+			})
+		);
+		// the pool over the asset limit is moved out of `Pools` along with its state
+		assert!(!unhashed::exists(&crate::Pools::<Test>::hashed_key_for(1)));
+		assert_eq!(
+			crate::migrations::v1::OversizedPools::<Test>::get(1).map(|pool| pool.encode()),
+			Some(old_pool_info(vec![1, 2, 3, 4, 5, 6]).encode())
+		);
+
+		// running it again is a no-op
+		let pool_info = crate::migrations::v1::Pools::<Test>::get(0);
+		crate::migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(crate::migrations::v1::Pools::<Test>::get(0), pool_info);
+		assert!(crate::migrations::v1::OversizedPools::<Test>::contains_key(1));
+
+		// later migrations decode every pool left in `Pools`
+		crate::migrations::v2::MigrateToV2::<Test>::on_runtime_upgrade();
+		assert!(crate::migrations::v2::Pools::<Test>::get(0).is_some());
+		assert!(!crate::migrations::v2::Pools::<Test>::contains_key(1));
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migrate_to_v1_try_runtime_checks() {
+	use frame_support::storage::unhashed;
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+		unhashed::put(
+			&crate::Pools::<Test>::hashed_key_for(1),
+			&old_pool_info(vec![1, 2, 3, 4, 5, 6]),
+		);
+		crate::PoolCount::<Test>::put(2);
+
+		// a pool over the asset limit fails the upgrade
+		assert!(crate::migrations::v1::MigrateToV1::<Test>::try_on_runtime_upgrade(true).is_err());
+
+		unhashed::put(&crate::Pools::<Test>::hashed_key_for(1), &old_pool_info(vec![1, 2, 3]));
+		assert_ok!(crate::migrations::v1::MigrateToV1::<Test>::try_on_runtime_upgrade(true));
+		assert_eq!(StableAsset::pool_count(), 2);
+		assert!(crate::migrations::v1::Pools::<Test>::get(0).is_some());
+		assert!(crate::migrations::v1::Pools::<Test>::get(1).is_some());
+	});
+}
+
+#[test]
+fn migrate_to_v2_successful() {
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		frame_support::storage::unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+
+		<(
+			crate::migrations::v1::MigrateToV1<Test>,
+			crate::migrations::v2::MigrateToV2<Test>,
+		)>::on_runtime_upgrade();
+
+		assert_eq!(StableAsset::on_chain_storage_version(), 2);
+		assert_eq!(
+			crate::migrations::v2::Pools::<Test>::get(0),
+			Some(crate::migrations::v2::StableAssetPoolInfoV2 {
+				pool_asset: 100,
+				assets: vec![1, 2].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
+				mint_fee: 10000000u128,
+				swap_fee: 20000000u128,
+				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				total_supply: 199999980000000000u128,
+				a: 10000u128,
+				a_block: 0,
+				future_a: 10000u128,
+				future_a_block: 0,
+				balances: vec![99999990000000000u128, 99999990000000000u128].try_into().unwrap(),
+				fee_recipient: 2,
+				account_id: 30160825295207673652903702381u128,
+				yield_recipient: 1,
+				precision: 1000000000000000000u128,
+			})
+		);
+
+		// running it again is a no-op
+		let pool_info = crate::migrations::v2::Pools::<Test>::get(0);
+		crate::migrations::v2::MigrateToV2::<Test>::on_runtime_upgrade();
+		assert_eq!(crate::migrations::v2::Pools::<Test>::get(0), pool_info);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migrate_to_v2_try_runtime_checks() {
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		frame_support::storage::unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+		crate::PoolCount::<Test>::put(1);
+
+		assert_ok!(<(
+			crate::migrations::v1::MigrateToV1<Test>,
+			crate::migrations::v2::MigrateToV2<Test>,
+		)>::try_on_runtime_upgrade(true));
+		assert_eq!(
+			crate::migrations::v2::Pools::<Test>::get(0)
+				.unwrap()
+				.offpeg_fee_multiplier,
+			0u128
+		);
+	});
+}
+
+#[test]
+fn migrate_to_v3_successful() {
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		frame_support::storage::unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+		<(
+			crate::migrations::v1::MigrateToV1<Test>,
+			crate::migrations::v2::MigrateToV2<Test>,
+		)>::on_runtime_upgrade();
+		let pool_asset = TestAssets::create_asset().expect("asset should be created");
+		assert_ok!(TestAssets::mint_into(pool_asset, &1, 199999980000000000u128));
+		crate::migrations::v2::Pools::<Test>::mutate(0, |pool_info| {
+			pool_info.as_mut().unwrap().pool_asset = pool_asset;
+		});
+
+		crate::migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+
+		assert_eq!(StableAsset::on_chain_storage_version(), 3);
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(pool_info.pool_asset, pool_asset);
+		assert_eq!(pool_info.admin_fee, 10000000000u128);
+		assert_eq!(pool_info.total_supply, 199999980000000000u128);
+		assert_eq!(pool_info.pool_token_supply, 199999980000000000u128);
+		assert_eq!(
+			pool_info.balances.to_vec(),
+			vec![99999990000000000u128, 99999990000000000u128]
+		);
+
+		// running it again is a no-op
+		crate::migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+		assert_eq!(StableAsset::pools(0), Some(pool_info));
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migrate_to_v3_try_runtime_checks() {
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<StableAsset>();
+		frame_support::storage::unhashed::put(&crate::Pools::<Test>::hashed_key_for(0), &old_pool_info(vec![1, 2]));
+		crate::PoolCount::<Test>::put(1);
+
+		assert_ok!(<(
+			crate::migrations::v1::MigrateToV1<Test>,
+			crate::migrations::v2::MigrateToV2<Test>,
+			crate::migrations::v3::MigrateToV3<Test>,
+		)>::try_on_runtime_upgrade(true));
+		assert_eq!(StableAsset::pools(0).unwrap().admin_fee, 10000000000u128);
+	});
+}
+
+#[test]
+fn migrate_to_v4_successful() {
+	use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, pool_asset, _swap_id) = create_pool();
+		StorageVersion::new(3).put::<StableAsset>();
+		crate::PoolAssetPools::<Test>::remove(pool_asset);
+
+		crate::migrations::v4::MigrateToV4::<Test>::on_runtime_upgrade();
+
+		assert_eq!(StableAsset::on_chain_storage_version(), 4);
+		assert_eq!(StableAsset::pool_asset_pool(pool_asset), Some(0));
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migrate_to_v4_try_runtime_checks() {
+	use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, pool_asset, _swap_id) = create_pool();
+		StorageVersion::new(3).put::<StableAsset>();
+		crate::PoolAssetPools::<Test>::remove(pool_asset);
+
+		assert_ok!(crate::migrations::v4::MigrateToV4::<Test>::try_on_runtime_upgrade(true));
+		assert_eq!(StableAsset::pool_asset_pool(pool_asset), Some(0));
+	});
+}
+
+#[test]
+fn try_state_holds_across_operations() {
+	new_test_ext().execute_with(|| {
+		let (coin0, _coin1, _pool_asset, swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::do_try_state());
+
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 20000000u128],
+			0
+		));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::swap_exact_output(
+			RuntimeOrigin::signed(1),
+			0,
+			1,
+			0,
+			1000000u128,
+			2000000u128,
+			2
+		));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			vec![0u128, 0u128]
+		));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::redeem_single(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			1,
+			0u128,
+			2
+		));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::redeem_multi(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![500000u128, 100000u128],
+			1100000000000000000u128
+		));
+		assert_ok!(StableAsset::do_try_state());
+		assert_ok!(StableAsset::set_pool_status(
+			RuntimeOrigin::signed(1),
+			0,
+			PoolStatus::Paused
+		));
+		assert_ok!(StableAsset::do_try_state());
+
+		// a small donation is pending yield
+		assert_ok!(TestAssets::mint_into(coin0, &swap_id, 10000u128));
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn try_state_detects_broken_invariants() {
+	new_test_ext().execute_with(|| {
+		let (coin0, _coin1, pool_asset, swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 20000000u128],
+			0
+		));
+
+		assert_ok!(TestAssets::mint_into(pool_asset, &3, 10000000000000000u128));
+		assert_err!(
+			StableAsset::do_try_state(),
+			"pool token issuance does not match pool token supply"
+		);
+		assert_ok!(TestAssets::burn_from(
+			pool_asset,
+			&3,
+			10000000000000000u128,
+			Preservation::Expendable,
+			Precision::Exact,
+			Fortitude::Polite
+		));
+		assert_ok!(StableAsset::do_try_state());
+
+		assert_ok!(TestAssets::burn_from(
+			coin0,
+			&swap_id,
+			1000u128,
+			Preservation::Expendable,
+			Precision::Exact,
+			Fortitude::Polite
+		));
+		assert_err!(
+			StableAsset::do_try_state(),
+			"pool balance is not backed by the pool account"
+		);
+		assert_ok!(TestAssets::mint_into(coin0, &swap_id, 1000u128));
+		assert_ok!(StableAsset::do_try_state());
+
+		crate::PoolCount::<Test>::put(0);
+		assert_err!(StableAsset::do_try_state(), "pool id is not below the pool count");
+	});
+}
+
+fn genesis_pool(
+	pool_asset: i64,
+	assets: Vec<i64>,
+	initial_liquidity: Option<(AccountId, Vec<Balance>)>,
+) -> GenesisPool<i64, u128, Balance, AccountId> {
+	GenesisPool {
+		pool_asset,
+		precisions: vec![10000000000u128; assets.len()],
+		assets,
+		mint_fee: 10000000u128,
+		swap_fee: 20000000u128,
+		redeem_fee: 50000000u128,
+		initial_a: 10000u128,
+		fee_recipient: 2,
+		yield_recipient: 1,
+		precision: 1000000000000000000u128,
+		initial_liquidity,
+	}
+}
+
+// The test assets are kept in storage, so genesis pools are built over the storage their assets were created in.
+fn build_genesis_pools(ext: &mut sp_io::TestExternalities, pools: Vec<GenesisPool<i64, u128, Balance, AccountId>>) {
+	ext.execute_with(|| crate::GenesisConfig::<Test> { pools }.build());
+}
+
+#[test]
+fn genesis_pools_successful() {
+	let mut ext = new_test_ext();
+	let (coin0, coin1, pool_asset, other_pool_asset) = ext.execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let pool_asset = TestAssets::create_asset().expect("asset should be created");
+		let other_pool_asset = TestAssets::create_asset().expect("asset should be created");
+		assert_ok!(TestAssets::mint_into(coin0, &1, 100_000_000));
+		assert_ok!(TestAssets::mint_into(coin1, &1, 100_000_000));
+		(coin0, coin1, pool_asset, other_pool_asset)
+	});
+
+	build_genesis_pools(
+		&mut ext,
+		vec![
+			genesis_pool(
+				pool_asset,
+				vec![coin0, coin1],
+				Some((1, vec![10000000u128, 20000000u128])),
+			),
+			genesis_pool(other_pool_asset, vec![coin0, coin1], None),
+		],
+	);
+	ext.execute_with(|| {
+		let swap_id = 30160825295207673652903702381u128;
+		assert_eq!(StableAsset::pool_count(), 2);
+		assert_eq!(
+			StableAsset::pools(0),
+			Some(StableAssetPoolInfo {
+				pool_asset,
+				assets: vec![coin0, coin1].try_into().unwrap(),
+				precisions: vec![10000000000u128, 10000000000u128].try_into().unwrap(),
+				mint_fee: 10000000u128,
+				swap_fee: 20000000u128,
+				redeem_fee: 50000000u128,
+				offpeg_fee_multiplier: 0u128,
+				admin_fee: 10000000000u128,
+				total_supply: 299906783104508635u128,
+				pool_token_supply: 299906803112262055u128,
+				a: 10000u128,
+				a_block: 0,
+				future_a: 10000u128,
+				future_a_block: 0,
+				balances: vec![99999990000000000u128, 199999990000000000u128].try_into().unwrap(),
+				fee_recipient: 2,
+				account_id: swap_id,
+				yield_recipient: 1,
+				precision: 1000000000000000000u128,
+			})
+		);
+		assert_eq!(
+			TestAssets::balance(pool_asset, &1),
+			299606896309149793u128 - BALANCE_OFF
+		);
+		assert_eq!(TestAssets::balance(coin0, &swap_id), 10000000u128 - BALANCE_OFF);
+		assert_eq!(TestAssets::balance(coin1, &swap_id), 20000000u128 - BALANCE_OFF);
+
+		let other_pool = StableAsset::pools(1).unwrap();
+		assert_eq!(other_pool.pool_asset, other_pool_asset);
+		assert_eq!(other_pool.total_supply, 0u128);
+		assert_eq!(other_pool.balances.to_vec(), vec![0u128, 0u128]);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis pool 0 cannot be created")]
+fn genesis_pools_invalid_pool_panics() {
+	let mut pool = genesis_pool(3, vec![1, 2], None);
+	pool.precisions = vec![1u128];
+	build_genesis_pools(&mut new_test_ext(), vec![pool]);
+}
+
+#[test]
+#[should_panic(expected = "message: Some(\"AOverMax\")")]
+fn genesis_pools_a_over_max_panics() {
+	let mut pool = genesis_pool(3, vec![1, 2], None);
+	pool.initial_a = 1000001u128;
+	build_genesis_pools(&mut new_test_ext(), vec![pool]);
+}
+
+#[test]
+#[should_panic(expected = "initial liquidity of genesis pool 0 cannot be minted")]
+fn genesis_pools_invalid_liquidity_panics() {
+	build_genesis_pools(
+		&mut new_test_ext(),
+		vec![genesis_pool(3, vec![1, 2], Some((1, vec![0u128, 0u128])))],
+	);
+}
+
+#[test]
+fn modify_offpeg_fee_multiplier_successful() {
+	new_test_ext().execute_with(|| {
+		create_pool();
+		System::set_block_number(1);
+
+		assert_ok!(StableAsset::modify_offpeg_fee_multiplier(
+			RuntimeOrigin::signed(1),
+			0,
+			500000000000u128
+		));
+		assert_eq!(StableAsset::pools(0).unwrap().offpeg_fee_multiplier, 500000000000u128);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::OffpegFeeMultiplierModified {
+				pool_id: 0,
+				offpeg_fee_multiplier: 500000000000u128,
+			})
+		);
+
+		// the swap fee times the multiplier is bounded by MaxSwapFee
+		assert_noop!(
+			StableAsset::modify_offpeg_fee_multiplier(RuntimeOrigin::signed(1), 0, 510000000000u128),
+			Error::<Test>::SwapFeeTooHigh
+		);
+		assert_noop!(
+			StableAsset::modify_fees(RuntimeOrigin::signed(1), 0, None, Some(20000001), None),
+			Error::<Test>::SwapFeeTooHigh
+		);
+		assert_noop!(
+			StableAsset::modify_offpeg_fee_multiplier(RuntimeOrigin::signed(1), 1, 0),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::modify_offpeg_fee_multiplier(RuntimeOrigin::none(), 0, 0),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn modify_admin_fee_successful() {
+	new_test_ext().execute_with(|| {
+		create_pool();
+		System::set_block_number(1);
+
+		assert_ok!(StableAsset::modify_admin_fee(
+			RuntimeOrigin::signed(1),
+			0,
+			5000000000u128
+		));
+		assert_eq!(StableAsset::pools(0).unwrap().admin_fee, 5000000000u128);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::AdminFeeModified {
+				pool_id: 0,
+				admin_fee: 5000000000u128,
+			})
+		);
+
+		assert_noop!(
+			StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 0, 10000000001u128),
+			Error::<Test>::AdminFeeTooHigh
+		);
+		assert_noop!(
+			StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 1, 0),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::modify_admin_fee(RuntimeOrigin::none(), 0, 0),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn admin_fee_split_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::modify_admin_fee(
+			RuntimeOrigin::signed(1),
+			0,
+			5000000000u128
+		));
+
+		// half of the mint fee goes to the fee recipient, the same mint pays 299906803112262 at a full admin fee
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 20000000u128],
+			0
+		));
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::Minted {
+			fee_amount,
+			lp_fee_amount,
+			output_amount,
+			..
+		}) = last_event()
+		{
+			assert_eq!(fee_amount, 149953401556131u128);
+			assert_eq!(lp_fee_amount, 149953401556131u128);
+			assert_eq!(output_amount, 299606896309149793u128);
+		} else {
+			panic!("Unexpected event");
+		}
+		assert_eq!(TestAssets::balance(pool_asset, &2), 149953401556131u128 - BALANCE_OFF);
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert!(pool_info.pool_token_supply < pool_info.total_supply);
+		assert_ok!(StableAsset::do_try_state());
+
+		// the share of the swap fee kept in the pool raises the value of each pool token
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::TokenSwapped {
+			fee_amount,
+			lp_fee_amount,
+			..
+		}) = last_event()
+		{
+			assert!(fee_amount > 0);
+			assert!(lp_fee_amount >= fee_amount && lp_fee_amount <= fee_amount + 1);
+		} else {
+			panic!("Unexpected event");
+		}
+		let swapped_pool_info = StableAsset::pools(0).unwrap();
+		assert!(TestAssets::balance(pool_asset, &2) > 149953401556131u128 - BALANCE_OFF);
+		assert!(
+			swapped_pool_info.total_supply * pool_info.pool_token_supply
+				> pool_info.total_supply * swapped_pool_info.pool_token_supply
+		);
+		assert_ok!(StableAsset::do_try_state());
+
+		// half of the redeem fee goes to the fee recipient, the other half is burned with the redeemed amount
+		let fee_recipient_balance = TestAssets::balance(pool_asset, &2);
+		let balance = TestAssets::balance(pool_asset, &1);
+		assert_ok!(StableAsset::redeem_single(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			0,
+			0u128,
+			2
+		));
+		if let RuntimeEvent::StableAsset(crate::pallet::Event::RedeemedSingle {
+			fee_amount,
+			lp_fee_amount,
+			..
+		}) = last_event()
+		{
+			assert_eq!(fee_amount, 250000000000000u128);
+			assert_eq!(lp_fee_amount, 250000000000000u128);
+		} else {
+			panic!("Unexpected event");
+		}
+		assert!(TestAssets::balance(pool_asset, &2) >= fee_recipient_balance + 250000000000000u128);
+		assert_eq!(TestAssets::balance(pool_asset, &1), balance - 100000000000000000u128);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn admin_fee_zero_keeps_fees_in_pool() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 0, 0));
+
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 20000000u128],
+			0
+		));
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		assert_ok!(StableAsset::redeem_proportion(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000000000u128,
+			vec![0u128, 0u128]
+		));
+		assert_eq!(TestAssets::balance(pool_asset, &2), 0);
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(pool_info.pool_token_supply, TestAssets::total_issuance(pool_asset));
+		assert!(pool_info.pool_token_supply < pool_info.total_supply);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn get_virtual_price_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		// pool tokens are worth one D each in a pool without any
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_virtual_price(0),
+			Some(1000000000000000000u128)
+		);
+		assert_eq!(<StableAsset as crate::traits::StableAsset>::get_virtual_price(1), None);
+
+		assert_ok!(StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), 0, 0));
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 20000000u128],
+			0
+		));
+		let pool_info = StableAsset::pools(0).unwrap();
+		let virtual_price = <StableAsset as crate::traits::StableAsset>::get_virtual_price(0).unwrap();
+		assert_eq!(
+			virtual_price,
+			pool_info.total_supply * 1000000000000000000u128 / pool_info.pool_token_supply
+		);
+
+		// the swap fee kept for the liquidity providers raises the virtual price
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		let new_virtual_price = <StableAsset as crate::traits::StableAsset>::get_virtual_price(0).unwrap();
+		assert!(new_virtual_price > virtual_price);
+		assert!(System::events().into_iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::StableAsset(crate::pallet::Event::FeeCollected { virtual_price, .. })
+				if virtual_price == new_virtual_price
+		)));
+	});
+}
+
+#[test]
+fn oracle_records_marginal_prices() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_eq!(StableAsset::pool_oracle(0), None);
+
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128],
+			0
+		));
+		let oracle = StableAsset::pool_oracle(0).unwrap();
+		assert_eq!(oracle.block, 2);
+		// a balanced pool prices both assets at one
+		assert_eq!(
+			oracle.prices.to_vec(),
+			vec![1000000000000000000u128, 1000000000000000000u128]
+		);
+		assert_eq!(oracle.price_cumulatives.to_vec(), vec![0u128, 0u128]);
+
+		// selling coin1 makes it cheaper than coin0, and the marginal price is between the swap price and
+		// one
+		System::set_block_number(5);
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 5000000u128, 0, 2));
+		let oracle = StableAsset::pool_oracle(0).unwrap();
+		assert_eq!(oracle.block, 5);
+		assert!(oracle.prices[1] < 1000000000000000000u128);
+		assert!(oracle.prices[1] > 900000000000000000u128);
+		assert_eq!(
+			oracle.price_cumulatives.to_vec(),
+			vec![3000000000000000000u128, 3000000000000000000u128]
+		);
+		assert_eq!(oracle.observation_index, 1);
+		assert_eq!(
+			crate::OracleObservations::<Test>::get(0, 1).unwrap().price_cumulatives,
+			oracle.price_cumulatives
+		);
+	});
+}
+
+#[test]
+fn get_twap_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128],
+			0
+		));
+		let initial_virtual_price = StableAsset::pool_oracle(0).unwrap().virtual_price;
+		System::set_block_number(12);
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 5000000u128, 0, 2));
+		let price = StableAsset::pool_oracle(0).unwrap().prices[1];
+		let virtual_price = StableAsset::pool_oracle(0).unwrap().virtual_price;
+
+		System::set_block_number(22);
+		let twap = |window: u64| <StableAsset as crate::traits::StableAsset>::get_twap(0, window);
+		assert_eq!(
+			twap(20),
+			Some((
+				vec![
+					1000000000000000000u128,
+					(10 * 1000000000000000000u128 + 10 * price) / 20
+				],
+				(10 * initial_virtual_price + 10 * virtual_price) / 20
+			))
+		);
+		// interpolated between the observations of blocks 2 and 12
+		assert_eq!(
+			twap(15).unwrap().0,
+			vec![1000000000000000000u128, (5 * 1000000000000000000u128 + 10 * price) / 15]
+		);
+		assert_eq!(twap(5).unwrap().0, vec![1000000000000000000u128, price]);
+
+		// a swap in the current block does not move the average
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 5000000u128, 0, 2));
+		assert!(StableAsset::pool_oracle(0).unwrap().prices[1] < price);
+		assert_eq!(twap(5).unwrap().0, vec![1000000000000000000u128, price]);
+
+		// before the oracle started
+		assert_eq!(twap(21), None);
+		assert_eq!(twap(0), None);
+		assert_eq!(<StableAsset as crate::traits::StableAsset>::get_twap(1, 5), None);
+	});
+}
+
+#[test]
+fn get_twap_limited_to_observations() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(2);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128],
+			0
+		));
+		// one observation per block, overwriting the oldest after MaxOracleObservations
+		for block in 3..=12 {
+			System::set_block_number(block);
+			assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 100000u128, 0, 2));
+		}
+		assert_eq!(crate::OracleObservations::<Test>::iter_prefix(0).count(), 10);
+		assert_eq!(StableAsset::pool_oracle(0).unwrap().observation_index, 0);
+		assert!(<StableAsset as crate::traits::StableAsset>::get_twap(0, 9).is_some());
+		assert_eq!(<StableAsset as crate::traits::StableAsset>::get_twap(0, 10), None);
+	});
+}
+
+#[test]
+fn get_ema_weight_successful() {
+	assert_eq!(StableAsset::get_ema_weight(0, 10), Some(1000000000000000000u128));
+	assert_eq!(StableAsset::get_ema_weight(10, 10), Some(500000000000000000u128));
+	assert_eq!(StableAsset::get_ema_weight(30, 10), Some(125000000000000000u128));
+	assert_eq!(StableAsset::get_ema_weight(640, 10), Some(0));
+	// 2^(-1/2)
+	let weight = StableAsset::get_ema_weight(1, 2).unwrap();
+	assert!(weight.abs_diff(707106781186547524u128) <= 10);
+	assert_eq!(StableAsset::get_ema_weight(1, 0), None);
+}
+
+#[test]
+fn get_ema_prices_successful() {
+	new_test_ext().execute_with(|| {
+		let (_coin0, _coin1, _pool_asset, _swap_id) = create_pool();
+		System::set_block_number(1);
+		assert_ok!(StableAsset::modify_ema_half_life(RuntimeOrigin::signed(1), 0, 10));
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::EmaHalfLifeModified {
+				pool_id: 0,
+				half_life: 10,
+			})
+		);
+		let ema_prices = || <StableAsset as crate::traits::StableAsset>::get_ema_prices(0);
+		assert_eq!(ema_prices(), None);
+
+		System::set_block_number(2);
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128],
+			0
+		));
+		assert_eq!(
+			ema_prices(),
+			Some(vec![1000000000000000000u128, 1000000000000000000u128])
+		);
+
+		// a swap in the current block does not move the EMA
+		System::set_block_number(12);
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 1, 0, 5000000u128, 0, 2));
+		let price = StableAsset::pool_oracle(0).unwrap().prices[1];
+		assert!(price < 1000000000000000000u128);
+		assert_eq!(
+			ema_prices(),
+			Some(vec![1000000000000000000u128, 1000000000000000000u128])
+		);
+
+		// half of the way to the spot price after one half-life
+		System::set_block_number(22);
+		assert_eq!(
+			ema_prices(),
+			Some(vec![1000000000000000000u128, 500000000000000000u128 + price / 2])
+		);
+		System::set_block_number(32);
+		assert_eq!(
+			ema_prices(),
+			Some(vec![1000000000000000000u128, 250000000000000000u128 + price * 3 / 4])
+		);
+
+		// the stored EMA carries on from the last update
+		assert_ok!(StableAsset::swap(RuntimeOrigin::signed(1), 0, 0, 1, 5000000u128, 0, 2));
+		assert_eq!(
+			crate::EmaPrices::<Test>::get(0).unwrap().to_vec(),
+			vec![1000000000000000000u128, 250000000000000000u128 + price * 3 / 4]
+		);
+		assert_eq!(<StableAsset as crate::traits::StableAsset>::get_ema_prices(1), None);
+	});
+}
+
+#[test]
+fn modify_ema_half_life_failed() {
+	new_test_ext().execute_with(|| {
+		create_pool();
+		System::set_block_number(1);
+
+		assert_noop!(
+			StableAsset::modify_ema_half_life(RuntimeOrigin::signed(1), 0, 0),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::modify_ema_half_life(RuntimeOrigin::signed(1), 1, 10),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::modify_ema_half_life(RuntimeOrigin::none(), 0, 10),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn add_asset_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+		let swap_id = StableAsset::pools(pool_id).unwrap().account_id;
+		let virtual_price = <StableAsset as crate::traits::StableAsset>::get_virtual_price(pool_id).unwrap();
+		let yield_recipient_balance = TestAssets::balance(pool_asset, &1);
+
+		assert_ok!(TestAssets::mint_into(coin2, &swap_id, 10000000u128));
+		let post_info = StableAsset::add_asset(RuntimeOrigin::signed(1), pool_id, coin2, 10000000000u128).unwrap();
+		// charged for the assets of the pool only
+		assert_eq!(post_info.actual_weight, Some(<() as crate::WeightInfo>::add_asset(2)));
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::AssetAdded {
+				pool_id,
+				asset: coin2,
+				precision: 10000000000u128,
+				balance: TestAssets::balance(coin2, &swap_id),
+			})
+		);
+		let pool_info = StableAsset::pools(pool_id).unwrap();
+		assert_eq!(pool_info.assets.to_vec(), vec![coin0, coin1, coin2]);
+		assert_eq!(pool_info.precisions.len(), 3);
+		assert_eq!(
+			pool_info.balances[2],
+			TestAssets::balance(coin2, &swap_id) * 10000000000u128
+		);
+		// the D added by the asset is minted to the yield recipient at the previous virtual price
+		assert!(TestAssets::balance(pool_asset, &1) > yield_recipient_balance);
+		assert!(<StableAsset as crate::traits::StableAsset>::get_virtual_price(pool_id).unwrap() >= virtual_price);
+		assert_eq!(StableAsset::pool_oracle(pool_id).unwrap().prices.len(), 3);
+		assert_ok!(StableAsset::do_try_state());
+
+		assert_ok!(TestAssets::mint_into(coin2, &1, 1000000u128));
+		assert_ok!(StableAsset::swap(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			2,
+			0,
+			1000000u128,
+			0,
+			3
+		));
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn add_asset_failed() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::signed(1), pool_id, coin1, 10000000000u128),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::signed(1), pool_id, pool_asset, 10000000000u128),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::signed(1), pool_id, coin2, 10000000000u128),
+			Error::<Test>::AssetNotFunded
+		);
+		// too little of the asset to keep D
+		let swap_id = StableAsset::pools(pool_id).unwrap().account_id;
+		assert_ok!(TestAssets::mint_into(coin2, &swap_id, 100u128));
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::signed(1), pool_id, coin2, 10000000000u128),
+			Error::<Test>::AssetNotFunded
+		);
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::signed(1), 1, coin2, 10000000000u128),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::add_asset(RuntimeOrigin::none(), pool_id, coin2, 10000000000u128),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn add_asset_to_empty_pool_successful() {
+	new_test_ext().execute_with(|| {
+		let (coin0, coin1, _pool_asset, _swap_id) = create_pool();
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		System::set_block_number(2);
+
+		assert_ok!(StableAsset::add_asset(
+			RuntimeOrigin::signed(1),
+			0,
+			coin2,
+			10000000000u128
+		));
+		let pool_info = StableAsset::pools(0).unwrap();
+		assert_eq!(pool_info.assets.to_vec(), vec![coin0, coin1, coin2]);
+		assert_eq!(pool_info.balances.to_vec(), vec![0u128, 0u128, 0u128]);
+
+		assert_ok!(TestAssets::mint_into(coin2, &1, 100000000u128));
+		assert_ok!(StableAsset::mint(
+			RuntimeOrigin::signed(1),
+			0,
+			vec![10000000u128, 10000000u128, 10000000u128],
+			0
+		));
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn remove_asset_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, _pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1, coin2],
+			vec![10000000000u128, 10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+		let swap_id = StableAsset::pools(pool_id).unwrap().account_id;
+
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 1),
+			Error::<Test>::AssetNotWoundDown
+		);
+
+		// wind coin1 down with outbound swaps only
+		let restrictions = PoolRestrictions::default()
+			.with_input_disabled(1)
+			.with_output_disabled(2);
+		assert_ok!(StableAsset::set_restrictions(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			restrictions
+		));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 9000000u128));
+		assert_ok!(StableAsset::swap(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			0,
+			1,
+			9000000u128,
+			0,
+			3
+		));
+		assert_noop!(
+			StableAsset::swap(RuntimeOrigin::signed(1), pool_id, 1, 0, 1000000u128, 0, 3),
+			Error::<Test>::AssetDisabled
+		);
+
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 1),
+			Error::<Test>::AssetNotDrained
+		);
+
+		// redeem coin1 down to dust
+		let remaining = TestAssets::balance(coin1, &swap_id);
+		assert_ok!(StableAsset::redeem_multi(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![0, remaining - 50000u128, 0],
+			100000000000000000000u128,
+		));
+		let virtual_price = <StableAsset as crate::traits::StableAsset>::get_virtual_price(pool_id).unwrap();
+		let pool_token_supply = StableAsset::pools(pool_id).unwrap().pool_token_supply;
+		let dust = TestAssets::balance(coin1, &swap_id);
+
+		let post_info = StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 1).unwrap();
+		// charged for the assets of the pool only
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::remove_asset(3))
+		);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::AssetRemoved {
+				pool_id,
+				asset: coin1,
+				who: 100,
+				amount: dust,
+			})
+		);
+		let pool_info = StableAsset::pools(pool_id).unwrap();
+		assert_eq!(pool_info.assets.to_vec(), vec![coin0, coin2]);
+		assert_eq!(pool_info.balances.len(), 2);
+		assert_eq!(TestAssets::balance(coin1, &swap_id), 0);
+		assert_eq!(TestAssets::balance(coin1, &100), dust - BALANCE_OFF);
+		// the liquidity providers keep their pool tokens, now without the dust or the imbalance it held
+		// D back by
+		assert_eq!(pool_info.pool_token_supply, pool_token_supply);
+		assert!(<StableAsset as crate::traits::StableAsset>::get_virtual_price(pool_id).unwrap() >= virtual_price);
+		// the later asset indexes move down
+		assert_eq!(
+			StableAsset::pool_restrictions(pool_id),
+			PoolRestrictions::default().with_output_disabled(1)
+		);
+		assert_ok!(StableAsset::do_try_state());
+
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 0),
+			Error::<Test>::ArgumentsError
+		);
+	});
+}
+
+#[test]
+fn remove_asset_failed() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let coin2 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, _pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1, coin2],
+			vec![10000000000u128, 10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+		assert_ok!(StableAsset::set_restrictions(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			PoolRestrictions::default().with_input_disabled(1)
+		));
+
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 3),
+			Error::<Test>::ArgumentsError
+		);
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), 1, 1),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::none(), pool_id, 1),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StableAsset::remove_asset(RuntimeOrigin::signed(1), pool_id, 1),
+			Error::<Test>::AssetNotDrained
+		);
+	});
+}
+
+#[test]
+fn pool_restrictions_without_index() {
+	let restrictions = PoolRestrictions::default()
+		.with_input_disabled(0)
+		.with_input_disabled(2)
+		.with_output_disabled(1)
+		.with_output_disabled(31);
+	assert_eq!(
+		restrictions.without_index(1),
+		PoolRestrictions::default()
+			.with_input_disabled(0)
+			.with_input_disabled(1)
+			.with_output_disabled(30)
+	);
+	assert_eq!(restrictions.without_index(40), restrictions);
+}
+
+#[test]
+fn destroy_pool_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		System::set_block_number(2);
+		assert_ok!(StableAsset::modify_ema_half_life(RuntimeOrigin::signed(1), pool_id, 10));
+		// no fee is minted to the fee recipient while the pool is drained
+		assert_ok!(StableAsset::modify_admin_fee(RuntimeOrigin::signed(1), pool_id, 0u128));
+		assert_ok!(StableAsset::modify_fees(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			None,
+			None,
+			Some(0u128)
+		));
+		let swap_id = StableAsset::pools(pool_id).unwrap().account_id;
+
+		let post_info = StableAsset::start_destroy_pool(RuntimeOrigin::signed(1), pool_id).unwrap();
+		// charged for the assets of the pool only
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::start_destroy_pool(2))
+		);
+		assert_eq!(StableAsset::pool_status(pool_id), PoolStatus::Destroying);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::PoolDestroying { pool_id })
+		);
+
+		// only redeems are allowed
+		assert_noop!(
+			StableAsset::mint(RuntimeOrigin::signed(1), pool_id, vec![1000000u128, 1000000u128], 0),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			StableAsset::swap(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, 0, 2),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			StableAsset::destroy_pool(RuntimeOrigin::signed(1), pool_id),
+			Error::<Test>::PoolNotDrained
+		);
+		assert_ok!(StableAsset::redeem_single(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			10000000000000000u128,
+			0,
+			0,
+			2
+		));
+		assert_ok!(StableAsset::redeem_multi(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![1000000u128, 1000000u128],
+			u128::MAX
+		));
+		for who in [1, 2] {
+			assert_ok!(StableAsset::redeem_proportion(
+				RuntimeOrigin::signed(who),
+				pool_id,
+				TestAssets::balance(pool_asset, &who),
+				vec![0u128, 0u128]
+			));
+		}
+		assert!(StableAsset::pools(pool_id).unwrap().pool_token_supply <= 1000000u128);
+
+		let dust0 = TestAssets::balance(coin0, &swap_id);
+		let dust1 = TestAssets::balance(coin1, &swap_id);
+		let post_info = StableAsset::destroy_pool(RuntimeOrigin::signed(1), pool_id).unwrap();
+		// charged for the assets of the pool only
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::destroy_pool(2))
+		);
+		assert_eq!(
+			last_event(),
+			RuntimeEvent::StableAsset(crate::pallet::Event::PoolDestroyed {
+				pool_id,
+				who: 100,
+				amounts: vec![dust0, dust1],
+			})
+		);
+		assert_eq!(StableAsset::pools(pool_id), None);
+		assert_eq!(StableAsset::pool_status(pool_id), PoolStatus::Active);
+		assert_eq!(StableAsset::pool_asset_pool(pool_asset), None);
+		assert_eq!(StableAsset::pool_oracle(pool_id), None);
+		assert_eq!(StableAsset::ema_half_life(pool_id), None);
+		assert_eq!(TestAssets::balance(coin0, &swap_id), 0);
+		assert_eq!(TestAssets::balance(coin1, &swap_id), 0);
+		// pool ids are not reused
+		assert_eq!(StableAsset::pool_count(), 1);
+		assert_ok!(StableAsset::do_try_state());
+
+		// the pool tokens left cannot be redeemed against a new pool of the pool asset
+		assert!(TestAssets::total_issuance(pool_asset) > 0);
+		let create_pool = || {
+			StableAsset::create_pool(
+				RuntimeOrigin::signed(1),
+				pool_asset,
+				vec![coin0, coin1],
+				vec![10000000000u128, 10000000000u128],
+				10000000u128,
+				20000000u128,
+				50000000u128,
+				10000u128,
+				2,
+				1,
+				1000000000000000000u128,
+			)
+		};
+		assert_noop!(create_pool(), Error::<Test>::PoolAssetInUse);
+		for who in [1, 2] {
+			let balance = TestAssets::balance(pool_asset, &who) + BALANCE_OFF;
+			assert_ok!(TestAssets::burn_from(
+				pool_asset,
+				&who,
+				balance,
+				Preservation::Expendable,
+				Precision::Exact,
+				Fortitude::Force
+			));
+		}
+		assert_eq!(TestAssets::total_issuance(pool_asset), 0);
+		assert_ok!(create_pool());
+	});
+}
+
+#[test]
+fn destroy_pool_failed() {
+	new_test_ext().execute_with(|| {
+		create_meta_pool();
+		System::set_block_number(2);
+
+		assert_noop!(
+			StableAsset::destroy_pool(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::PoolNotDestroying
+		);
+		assert_ok!(StableAsset::start_destroy_pool(RuntimeOrigin::signed(1), 0));
+		assert_noop!(
+			StableAsset::start_destroy_pool(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::PoolDestroying
+		);
+		assert_noop!(
+			StableAsset::set_pool_status(RuntimeOrigin::signed(1), 0, PoolStatus::Active),
+			Error::<Test>::PoolDestroying
+		);
+		assert_noop!(
+			StableAsset::set_pool_status(RuntimeOrigin::signed(1), 1, PoolStatus::Destroying),
+			Error::<Test>::PoolDestroying
+		);
+		// the metapool still holds the base pool token
+		assert_noop!(
+			StableAsset::destroy_pool(RuntimeOrigin::signed(1), 0),
+			Error::<Test>::PoolAssetInUse
+		);
+		assert_noop!(
+			StableAsset::destroy_pool(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::start_destroy_pool(RuntimeOrigin::signed(1), 2),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::start_destroy_pool(RuntimeOrigin::none(), 1),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StableAsset::destroy_pool(RuntimeOrigin::none(), 0),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn deadline_passed() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, _pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_ok!(TestAssets::mint_into(coin0, &1, 10000000u128));
+		System::set_block_number(3);
+
+		assert_noop!(
+			StableAsset::mint_v2(RuntimeOrigin::signed(1), pool_id, vec![1000000u128, 0], 0, Some(2)),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::swap_v2(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, 0, Some(2)),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::swap_exact_output_v2(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				0,
+				1,
+				1000000u128,
+				2000000u128,
+				Some(2)
+			),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::swap_route_v2(
+				RuntimeOrigin::signed(1),
+				vec![SwapRouteStep::Swap {
+					pool_id,
+					input_index: 0,
+					output_index: 1,
+				}],
+				1000000u128,
+				0,
+				Some(2)
+			),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::swap_underlying_v2(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, 0, Some(2)),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::redeem_proportion_v2(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				1000000u128,
+				vec![0u128, 0u128],
+				Some(2)
+			),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::redeem_single_v2(RuntimeOrigin::signed(1), pool_id, 1000000u128, 0, 0, Some(2)),
+			Error::<Test>::DeadlinePassed
+		);
+		assert_noop!(
+			StableAsset::redeem_multi_v2(
+				RuntimeOrigin::signed(1),
+				pool_id,
+				vec![1000u128, 1000u128],
+				u128::MAX,
+				Some(2)
+			),
+			Error::<Test>::DeadlinePassed
+		);
+
+		// executes up to and including the deadline block
+		assert_ok!(StableAsset::swap_v2(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			0,
+			1,
+			1000000u128,
+			0,
+			Some(3)
+		));
+		assert_ok!(StableAsset::mint_v2(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			vec![1000000u128, 0],
+			0,
+			None
+		));
+	});
+}
+
+#[test]
+fn swap_v2_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, pool_asset) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_ok!(TestAssets::mint_into(coin0, &1, 10000000u128));
+		assert_ok!(TestAssets::mint_into(coin1, &1, 10000000u128));
+		System::set_block_number(2);
+		let pool_info = StableAsset::pools(pool_id).unwrap();
+		let swap_result = StableAsset::get_swap_amount(&pool_info, 0, 1, 1000000u128).unwrap();
+		let coin1_balance = TestAssets::balance(coin1, &1);
+
+		// charged for the two assets of the pool
+		let (post_info, iterations) = StableAsset::with_newton_iterations(|| {
+			StableAsset::swap_v2(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, 0, None).unwrap()
+		});
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::swap(2) + newton_iterations_weight(2, iterations))
+		);
+		assert_eq!(TestAssets::balance(coin1, &1), coin1_balance + swap_result.dy);
+
+		let (post_info, iterations) = StableAsset::with_newton_iterations(|| {
+			StableAsset::swap_exact_output_v2(RuntimeOrigin::signed(1), pool_id, 0, 1, 500000u128, 1000000u128, None)
+				.unwrap()
+		});
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::swap_exact_output(2) + newton_iterations_weight(2, iterations))
+		);
+		assert_eq!(
+			TestAssets::balance(coin1, &1),
+			coin1_balance + swap_result.dy + 500000u128
+		);
+
+		let pool_token_balance = TestAssets::balance(pool_asset, &1);
+		let (post_info, iterations) = StableAsset::with_newton_iterations(|| {
+			StableAsset::redeem_single_v2(RuntimeOrigin::signed(1), pool_id, 1000000000000000u128, 0, 0, None).unwrap()
+		});
+		assert_eq!(
+			post_info.actual_weight,
+			Some(<() as crate::WeightInfo>::redeem_single(2) + newton_iterations_weight(2, iterations))
+		);
+		assert_eq!(
+			TestAssets::balance(pool_asset, &1),
+			pool_token_balance - 1000000000000000u128
+		);
+		assert_ok!(StableAsset::do_try_state());
+
+		assert_noop!(
+			StableAsset::swap_v2(RuntimeOrigin::signed(1), 1, 0, 1, 1000000u128, 0, None),
+			Error::<Test>::PoolNotFound
+		);
+		assert_noop!(
+			StableAsset::redeem_single_v2(RuntimeOrigin::signed(1), pool_id, 1000000u128, 0, 0, Some(1)),
+			Error::<Test>::DeadlinePassed
+		);
+	});
+}
+
+#[test]
+fn get_dynamic_fee_successful() {
+	new_test_ext().execute_with(|| {
+		// a multiplier at or below FeePrecision keeps the fee flat
+		assert_eq!(StableAsset::get_dynamic_fee(1, 3, 20000000, 0), Some(20000000));
+		assert_eq!(
+			StableAsset::get_dynamic_fee(1, 3, 20000000, 10000000000),
+			Some(20000000)
+		);
+		// balanced
+		assert_eq!(
+			StableAsset::get_dynamic_fee(5, 5, 20000000, 100000000000),
+			Some(20000000)
+		);
+		// 1 - 4 * 1 * 3 / 16 of the way to fee * 10
+		assert_eq!(
+			StableAsset::get_dynamic_fee(1, 3, 20000000, 100000000000),
+			Some(25806451)
+		);
+		assert_eq!(
+			StableAsset::get_dynamic_fee(0, 3, 20000000, 100000000000),
+			Some(200000000)
+		);
+	});
+}
+
+#[test]
+fn swap_dynamic_fee_successful() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_ok!(StableAsset::modify_offpeg_fee_multiplier(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000u128
+		));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 10000000u128));
+		System::set_block_number(2);
+
+		let flat = StableAsset::get_swap_amount(&StableAsset::pools(1).unwrap(), 0, 1, 5000000u128).unwrap();
+		let dynamic = StableAsset::get_swap_amount(&StableAsset::pools(0).unwrap(), 0, 1, 5000000u128).unwrap();
+		assert!(dynamic.dy < flat.dy);
+		assert_eq!(dynamic.y, flat.y);
+
+		// the flat fee pool gives the better quote
+		assert_eq!(
+			<StableAsset as crate::traits::StableAsset>::get_best_route(coin0, coin1, 5000000u128),
+			Some((1, 0, 1, flat.dy))
+		);
+
+		// the exact output quote covers the dynamic fee
+		let exact = StableAsset::get_swap_amount_exact(&StableAsset::pools(0).unwrap(), 0, 1, dynamic.dy).unwrap();
+		assert!(exact.dx >= 5000000u128);
+		assert_ok!(StableAsset::swap_exact_output(
+			RuntimeOrigin::signed(1),
+			0,
+			0,
+			1,
+			dynamic.dy,
+			exact.dx,
+			2
+		));
+		assert_ok!(StableAsset::do_try_state());
+
+		// swapping back towards balance is charged less than the flat fee pool charges for the first swap
+		let pool_info = StableAsset::pools(0).unwrap();
+		let balances = StableAsset::convert_vec_balance_to_number(pool_info.balances.to_vec());
+		let back = StableAsset::get_swap_amount(&pool_info, 1, 0, 1000000u128).unwrap();
+		let fee = StableAsset::get_dynamic_fee(
+			(balances[1] + back.balance_i) / 2,
+			(balances[0] + back.y) / 2,
+			pool_info.swap_fee,
+			pool_info.offpeg_fee_multiplier,
+		)
+		.unwrap();
+		let forward_fee = StableAsset::get_dynamic_fee(
+			(balances[0] + flat.balance_i) / 2,
+			(balances[1] + flat.y) / 2,
+			pool_info.swap_fee,
+			pool_info.offpeg_fee_multiplier,
+		)
+		.unwrap();
+		assert!(fee > pool_info.swap_fee);
+		assert!(fee < forward_fee);
+	});
+}
+
+#[test]
+fn swap_exact_output_dynamic_fee_covers_output() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![90000000u128, 10000000u128],
+		);
+		assert_ok!(StableAsset::modify_offpeg_fee_multiplier(
+			RuntimeOrigin::signed(1),
+			0,
+			100000000000u128
+		));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 20000000u128));
+		assert_ok!(TestAssets::mint_into(coin1, &1, 1u128));
+		System::set_block_number(2);
+
+		// the quote at the estimated dynamic fee falls short of the output, the exact quote is raised to cover it
+		let pool_info = StableAsset::pools(0).unwrap();
+		let exact = StableAsset::get_swap_amount_exact(&pool_info, 0, 1, 8100000u128).unwrap();
+		assert!(exact.dx > 10786032u128);
+		assert!(StableAsset::get_swap_amount(&pool_info, 0, 1, 10786032u128).unwrap().dy < 8100000u128);
+		assert!(StableAsset::get_swap_amount(&pool_info, 0, 1, exact.dx).unwrap().dy >= 8100000u128);
+
+		let coin0_balance = TestAssets::balance(coin0, &1);
+		let coin1_balance = TestAssets::balance(coin1, &1);
+		assert_ok!(StableAsset::swap_exact_output(
+			RuntimeOrigin::signed(1),
+			0,
+			0,
+			1,
+			8100000u128,
+			exact.dx,
+			2
+		));
+		assert_eq!(TestAssets::balance(coin0, &1), coin0_balance - exact.dx);
+		assert_eq!(TestAssets::balance(coin1, &1), coin1_balance + 8100000u128);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
+
+#[test]
+fn newton_iterations_refunded() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, _) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![10000000u128, 10000000u128],
+		);
+		assert_ok!(TestAssets::mint_into(coin0, &1, 10000000u128));
+		assert_ok!(TestAssets::mint_into(coin1, &1, 10000000u128));
+
+		let swap = RuntimeCall::StableAsset(crate::Call::swap {
+			pool_id,
+			i: 0,
+			j: 1,
+			dx: 1000000u128,
+			min_dy: 0,
+			asset_length: 2,
+		});
+		// every call of get_d and get_y may take the most iterations
+		assert_eq!(
+			swap.get_dispatch_info().weight,
+			<() as crate::WeightInfo>::swap(2)
+				+ newton_iterations_weight(
+					2,
+					crate::MAX_SOLVES_PER_OPERATION * crate::NUMBER_OF_ITERATIONS_TO_CONVERGE as u32
+				)
+		);
+		let (post_info, iterations) = StableAsset::with_newton_iterations(|| {
+			StableAsset::swap(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, 0, 2).unwrap()
+		});
+		assert!(iterations > 0);
+		let actual_weight = post_info.actual_weight.unwrap();
+		assert_eq!(
+			actual_weight,
+			<() as crate::WeightInfo>::swap(2) + newton_iterations_weight(2, iterations)
+		);
+		assert!(actual_weight.ref_time() < swap.get_dispatch_info().weight.ref_time());
+
+		let mint = RuntimeCall::StableAsset(crate::Call::mint {
+			pool_id,
+			amounts: vec![1000000u128, 2000000u128],
+			min_mint_amount: 0,
+		});
+		let (post_info, iterations) = StableAsset::with_newton_iterations(|| {
+			StableAsset::mint(RuntimeOrigin::signed(1), pool_id, vec![1000000u128, 2000000u128], 0).unwrap()
+		});
+		let actual_weight = post_info.actual_weight.unwrap();
+		assert_eq!(
+			actual_weight,
+			<() as crate::WeightInfo>::mint(2) + newton_iterations_weight(2, iterations)
+		);
+		assert!(actual_weight.ref_time() < mint.get_dispatch_info().weight.ref_time());
+		assert_ok!(StableAsset::do_try_state());
+
+		// failed operations are charged in full
+		let error = StableAsset::swap(RuntimeOrigin::signed(1), pool_id, 0, 1, 1000000u128, u128::MAX, 2).unwrap_err();
+		assert_eq!(error.error, Error::<Test>::SwapUnderMin.into());
+		assert_eq!(error.post_info.actual_weight, None);
+	});
+}
+
+#[test]
+fn swap_exact_output_solves_bounded() {
+	new_test_ext().execute_with(|| {
+		let coin0 = TestAssets::create_asset().expect("asset should be created");
+		let coin1 = TestAssets::create_asset().expect("asset should be created");
+		let (pool_id, _) = create_pool_with_liquidity(
+			vec![coin0, coin1],
+			vec![10000000000u128, 10000000000u128],
+			vec![90000000u128, 10000000u128],
+		);
+		assert_ok!(StableAsset::modify_offpeg_fee_multiplier(
+			RuntimeOrigin::signed(1),
+			pool_id,
+			100000000000u128
+		));
+		assert_ok!(TestAssets::mint_into(coin0, &1, 100000000u128));
+
+		// every estimate of the dynamic fee falls short of the output, so the quote takes every adjustment
+		let pool_info = StableAsset::pools(pool_id).unwrap();
+		let (exact, count) =
+			StableAsset::with_newton_count(|| StableAsset::get_swap_amount_exact(&pool_info, 0, 1, 8800000u128));
+		assert!(exact.is_some());
+		assert_eq!(count.solves, 3 + 2 * crate::MAX_EXACT_OUTPUT_ADJUSTMENTS);
+
+		// collecting the yield at a changed rate solves for the recorded balances too
+		set_rate(coin0, RATE_PRECISION / 10 * 11);
+		let (result, count) = StableAsset::with_newton_count(|| {
+			StableAsset::swap_exact_output(RuntimeOrigin::signed(1), pool_id, 0, 1, 8800000u128, u128::MAX, 2)
+		});
+		assert_ok!(result);
+		assert!(count.solves <= crate::MAX_SOLVES_PER_EXACT_OUTPUT);
+		assert_ok!(StableAsset::do_try_state());
+	});
+}
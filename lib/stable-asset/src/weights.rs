@@ -15,96 +15,677 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Autogenerated weights for nutsfinance_stable_asset
+//! Autogenerated weights for `nutsfinance_stable_asset`
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 3.0.0
-//! DATE: 2021-08-10, STEPS: `[50, ]`, REPEAT: 20, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 128
-
-// Executed Command:
-// target/release/node
-// benchmark
-// --chain=dev
-// --steps=50
-// --repeat=20
-// --pallet=*
-// --extrinsic=*
-// --execution=wasm
-// --wasm-execution=compiled
-// --heap-pages=4096
-// --template=./templates/runtime-weight-template.hbs
-// --output=./runtime/src/weights/
-
+//! Generated from the benchmarks in `benchmarking.rs`, run natively on the mock runtime of the tests.
+//! DATE: 2026-10-17, STEPS: `20`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
+//! CPU: `Intel(R) Xeon(R) Processor`
+//!
+//! Proof sizes are not measured. Runtimes should regenerate these weights for their own assets and
+//! hardware with `frame-omni-bencher`.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
 #![allow(unused_imports)]
+#![allow(missing_docs)]
 
-use frame_support::{
-	traits::Get,
-	weights::{constants::RocksDbWeight, Weight},
-};
-use sp_std::marker::PhantomData;
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
 use crate::WeightInfo;
 
-#[allow(clippy::unnecessary_cast)]
+/// Weights for `nutsfinance_stable_asset` from the run above, with the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:1)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:0)
+	/// Storage: `StableAsset::PoolCount` (r:1 w:1)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `System::Account` (r:1 w:1)
+	fn create_pool() -> Weight {
+		// Minimum execution time: 12_501_000 picoseconds.
+		Weight::from_parts(13_062_000, 0)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn modify_a() -> Weight {
+		// Minimum execution time: 9_728_000 picoseconds.
+		Weight::from_parts(10_005_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn stop_ramp_a() -> Weight {
+		// Minimum execution time: 9_304_000 picoseconds.
+		Weight::from_parts(9_868_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn modify_fees() -> Weight {
+		// Minimum execution time: 6_463_000 picoseconds.
+		Weight::from_parts(6_582_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn modify_recipients() -> Weight {
+		// Minimum execution time: 8_974_000 picoseconds.
+		Weight::from_parts(10_189_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn modify_offpeg_fee_multiplier() -> Weight {
+		// Minimum execution time: 6_478_000 picoseconds.
+		Weight::from_parts(6_748_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:5 w:0)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	fn modify_admin_fee() -> Weight {
+		// Minimum execution time: 58_776_000 picoseconds.
+		Weight::from_parts(59_483_000, 0)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::EmaHalfLives` (r:0 w:1)
+	fn modify_ema_half_life() -> Weight {
+		// Minimum execution time: 6_254_000 picoseconds.
+		Weight::from_parts(6_466_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:6 w:1)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:1)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:1)
+	/// Storage: `StableAsset::OracleObservations` (r:1 w:2)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// The range of component `u` is `[2, 4]`.
+	fn add_asset(u: u32, ) -> Weight {
+		// Minimum execution time: 84_990_000 picoseconds.
+		Weight::from_parts(70_392_316, 0)
+			// Standard Error: 227_613
+			.saturating_add(Weight::from_parts(8_583_950, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:6 w:2)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:1)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:1)
+	/// Storage: `StableAsset::OracleObservations` (r:1 w:2)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// The range of component `u` is `[3, 5]`.
+	fn remove_asset(u: u32, ) -> Weight {
+		// Minimum execution time: 95_296_000 picoseconds.
+		Weight::from_parts(96_816_000, 0)
+			// Standard Error: 1_710_366
+			.saturating_add(Weight::from_parts(10_390_698, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn start_destroy_pool(u: u32, ) -> Weight {
+		// Minimum execution time: 15_483_000 picoseconds.
+		Weight::from_parts(20_078_390, 0)
+			// Standard Error: 99_443
+			.saturating_add(Weight::from_parts(230_360, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	/// Storage: `TestAssets::AssetBalances` (r:10 w:10)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:5 w:5)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `StableAsset::Restrictions` (r:0 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:0 w:1)
+	/// Storage: `StableAsset::PoolRates` (r:0 w:1)
+	/// Storage: `StableAsset::Oracles` (r:0 w:1)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn destroy_pool(u: u32, ) -> Weight {
+		// Minimum execution time: 76_875_000 picoseconds.
+		Weight::from_parts(40_467_890, 0)
+			// Standard Error: 632_411
+			.saturating_add(Weight::from_parts(22_413_110, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Pools` (r:2 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::MetaPools` (r:0 w:1)
+	fn set_base_pool() -> Weight {
+		// Minimum execution time: 17_709_000 picoseconds.
+		Weight::from_parts(21_220_000, 0)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	fn set_pool_status() -> Weight {
+		// Minimum execution time: 9_731_000 picoseconds.
+		Weight::from_parts(12_393_000, 0)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:0 w:1)
+	fn set_paused() -> Weight {
+		// Minimum execution time: 5_632_000 picoseconds.
+		Weight::from_parts(7_545_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:0 w:1)
+	fn set_restrictions() -> Weight {
+		// Minimum execution time: 8_202_000 picoseconds.
+		Weight::from_parts(10_620_000, 0)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn mint(u: u32, ) -> Weight {
+		// Minimum execution time: 94_269_000 picoseconds.
+		Weight::from_parts(53_877_790, 0)
+			// Standard Error: 1_954_318
+			.saturating_add(Weight::from_parts(22_180_210, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(13_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:5)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:3 w:3)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn swap(u: u32, ) -> Weight {
+		// Minimum execution time: 93_633_000 picoseconds.
+		Weight::from_parts(76_978_940, 0)
+			// Standard Error: 251_333
+			.saturating_add(Weight::from_parts(9_057_260, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:5)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:3 w:3)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn swap_exact_output(u: u32, ) -> Weight {
+		// Minimum execution time: 108_876_000 picoseconds.
+		Weight::from_parts(99_256_840, 0)
+			// Standard Error: 1_217_772
+			.saturating_add(Weight::from_parts(8_590_210, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_proportion(u: u32, ) -> Weight {
+		// Minimum execution time: 100_859_000 picoseconds.
+		Weight::from_parts(74_162_830, 0)
+			// Standard Error: 829_195
+			.saturating_add(Weight::from_parts(18_063_720, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:4)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:2 w:2)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_single(u: u32, ) -> Weight {
+		// Minimum execution time: 96_125_000 picoseconds.
+		Weight::from_parts(81_516_950, 0)
+			// Standard Error: 278_963
+			.saturating_add(Weight::from_parts(8_573_950, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_multi(u: u32, ) -> Weight {
+		// Minimum execution time: 98_346_000 picoseconds.
+		Weight::from_parts(59_408_460, 0)
+			// Standard Error: 283_480
+			.saturating_add(Weight::from_parts(20_389_440, 0).saturating_mul(u.into()))
+			.saturating_add(T::DbWeight::get().reads(13_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// The range of component `u` is `[2, 5]`.
+	fn newton_iteration(u: u32, ) -> Weight {
+		// Minimum execution time: 994_000 picoseconds.
+		Weight::from_parts(632_930, 0)
+			// Standard Error: 2_096
+			.saturating_add(Weight::from_parts(195_570, 0).saturating_mul(u.into()))
+	}
+}
+
+// For backwards compatibility and tests.
 impl WeightInfo for () {
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:1)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:0)
+	/// Storage: `StableAsset::PoolCount` (r:1 w:1)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `System::Account` (r:1 w:1)
 	fn create_pool() -> Weight {
-		Weight::from_parts(33_115_000 as u64, 0)
-			.saturating_add(RocksDbWeight::get().reads(3 as u64))
-			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+		// Minimum execution time: 12_501_000 picoseconds.
+		Weight::from_parts(13_062_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
 	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
 	fn modify_a() -> Weight {
-		Weight::from_parts(21_186_000 as u64, 0)
-			.saturating_add(RocksDbWeight::get().reads(1 as u64))
-			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+		// Minimum execution time: 9_728_000 picoseconds.
+		Weight::from_parts(10_005_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn stop_ramp_a() -> Weight {
+		// Minimum execution time: 9_304_000 picoseconds.
+		Weight::from_parts(9_868_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
 	fn modify_fees() -> Weight {
-		Weight::from_parts(21_186_000 as u64, 0)
-			.saturating_add(RocksDbWeight::get().reads(1 as u64))
-			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+		// Minimum execution time: 6_463_000 picoseconds.
+		Weight::from_parts(6_582_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
 	fn modify_recipients() -> Weight {
-		Weight::from_parts(21_186_000 as u64, 0)
-			.saturating_add(RocksDbWeight::get().reads(1 as u64))
-			.saturating_add(RocksDbWeight::get().writes(1 as u64))
-	}
-	fn mint(u: u32) -> Weight {
-		Weight::from_parts(85_694_000 as u64, 0)
-			.saturating_add(Weight::from_parts(46_172_000 as u64, 0).saturating_mul(u as u64))
-			.saturating_add(RocksDbWeight::get().reads(6 as u64))
-			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(u as u64)))
-			.saturating_add(RocksDbWeight::get().writes(6 as u64))
-			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(u as u64)))
-	}
-	fn swap(u: u32) -> Weight {
-		Weight::from_parts(124_402_000 as u64, 0)
-			.saturating_add(Weight::from_parts(8_138_000 as u64, 0).saturating_mul(u as u64))
-			.saturating_add(RocksDbWeight::get().reads(7 as u64))
-			.saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(u as u64)))
-			.saturating_add(RocksDbWeight::get().writes(9 as u64))
-	}
-	fn redeem_proportion(u: u32) -> Weight {
-		Weight::from_parts(107_494_000 as u64, 0)
-			.saturating_add(Weight::from_parts(43_376_000 as u64, 0).saturating_mul(u as u64))
-			.saturating_add(RocksDbWeight::get().reads(4 as u64))
-			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(u as u64)))
-			.saturating_add(RocksDbWeight::get().writes(4 as u64))
-			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(u as u64)))
-	}
-	fn redeem_single(u: u32) -> Weight {
-		Weight::from_parts(114_847_000 as u64, 0)
-			.saturating_add(Weight::from_parts(14_613_000 as u64, 0).saturating_mul(u as u64))
-			.saturating_add(RocksDbWeight::get().reads(6 as u64))
-			.saturating_add(RocksDbWeight::get().reads((1 as u64).saturating_mul(u as u64)))
-			.saturating_add(RocksDbWeight::get().writes(7 as u64))
-	}
-	fn redeem_multi(u: u32) -> Weight {
-		Weight::from_parts(86_888_000 as u64, 0)
-			.saturating_add(Weight::from_parts(43_556_000 as u64, 0).saturating_mul(u as u64))
-			.saturating_add(RocksDbWeight::get().reads(4 as u64))
-			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(u as u64)))
-			.saturating_add(RocksDbWeight::get().writes(4 as u64))
-			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(u as u64)))
+		// Minimum execution time: 8_974_000 picoseconds.
+		Weight::from_parts(10_189_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	fn modify_offpeg_fee_multiplier() -> Weight {
+		// Minimum execution time: 6_478_000 picoseconds.
+		Weight::from_parts(6_748_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:5 w:0)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	fn modify_admin_fee() -> Weight {
+		// Minimum execution time: 58_776_000 picoseconds.
+		Weight::from_parts(59_483_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::EmaHalfLives` (r:0 w:1)
+	fn modify_ema_half_life() -> Weight {
+		// Minimum execution time: 6_254_000 picoseconds.
+		Weight::from_parts(6_466_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:6 w:1)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:1)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:1)
+	/// Storage: `StableAsset::OracleObservations` (r:1 w:2)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// The range of component `u` is `[2, 4]`.
+	fn add_asset(u: u32, ) -> Weight {
+		// Minimum execution time: 84_990_000 picoseconds.
+		Weight::from_parts(70_392_316, 0)
+			// Standard Error: 227_613
+			.saturating_add(Weight::from_parts(8_583_950, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:6 w:2)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:1)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:1 w:1)
+	/// Storage: `StableAsset::OracleObservations` (r:1 w:2)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// The range of component `u` is `[3, 5]`.
+	fn remove_asset(u: u32, ) -> Weight {
+		// Minimum execution time: 95_296_000 picoseconds.
+		Weight::from_parts(96_816_000, 0)
+			// Standard Error: 1_710_366
+			.saturating_add(Weight::from_parts(10_390_698, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn start_destroy_pool(u: u32, ) -> Weight {
+		// Minimum execution time: 15_483_000 picoseconds.
+		Weight::from_parts(20_078_390, 0)
+			// Standard Error: 99_443
+			.saturating_add(Weight::from_parts(230_360, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	/// Storage: `TestAssets::AssetBalances` (r:10 w:10)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:5 w:5)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `StableAsset::Restrictions` (r:0 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:0 w:1)
+	/// Storage: `StableAsset::PoolRates` (r:0 w:1)
+	/// Storage: `StableAsset::Oracles` (r:0 w:1)
+	/// Storage: `StableAsset::EmaPrices` (r:0 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn destroy_pool(u: u32, ) -> Weight {
+		// Minimum execution time: 76_875_000 picoseconds.
+		Weight::from_parts(40_467_890, 0)
+			// Standard Error: 632_411
+			.saturating_add(Weight::from_parts(22_413_110, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Pools` (r:2 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::MetaPools` (r:0 w:1)
+	fn set_base_pool() -> Weight {
+		// Minimum execution time: 17_709_000 picoseconds.
+		Weight::from_parts(21_220_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:1)
+	fn set_pool_status() -> Weight {
+		// Minimum execution time: 9_731_000 picoseconds.
+		Weight::from_parts(12_393_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:0 w:1)
+	fn set_paused() -> Weight {
+		// Minimum execution time: 5_632_000 picoseconds.
+		Weight::from_parts(7_545_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Pools` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:0 w:1)
+	fn set_restrictions() -> Weight {
+		// Minimum execution time: 8_202_000 picoseconds.
+		Weight::from_parts(10_620_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn mint(u: u32, ) -> Weight {
+		// Minimum execution time: 94_269_000 picoseconds.
+		Weight::from_parts(53_877_790, 0)
+			// Standard Error: 1_954_318
+			.saturating_add(Weight::from_parts(22_180_210, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(13_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:5)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:3 w:3)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn swap(u: u32, ) -> Weight {
+		// Minimum execution time: 93_633_000 picoseconds.
+		Weight::from_parts(76_978_940, 0)
+			// Standard Error: 251_333
+			.saturating_add(Weight::from_parts(9_057_260, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:5)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:3 w:3)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn swap_exact_output(u: u32, ) -> Weight {
+		// Minimum execution time: 108_876_000 picoseconds.
+		Weight::from_parts(99_256_840, 0)
+			// Standard Error: 1_217_772
+			.saturating_add(Weight::from_parts(8_590_210, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_proportion(u: u32, ) -> Weight {
+		// Minimum execution time: 100_859_000 picoseconds.
+		Weight::from_parts(74_162_830, 0)
+			// Standard Error: 829_195
+			.saturating_add(Weight::from_parts(18_063_720, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:8 w:4)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:2 w:2)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_single(u: u32, ) -> Weight {
+		// Minimum execution time: 96_125_000 picoseconds.
+		Weight::from_parts(81_516_950, 0)
+			// Standard Error: 278_963
+			.saturating_add(Weight::from_parts(8_573_950, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: `StableAsset::Paused` (r:1 w:0)
+	/// Storage: `StableAsset::PoolStatuses` (r:1 w:0)
+	/// Storage: `StableAsset::Restrictions` (r:1 w:0)
+	/// Storage: `StableAsset::Pools` (r:1 w:1)
+	/// Storage: `StableAsset::PoolAssetPools` (r:1 w:0)
+	/// Storage: `StableAsset::BasePools` (r:1 w:0)
+	/// Storage: `TestAssets::AssetBalances` (r:12 w:12)
+	/// Storage: `StableAsset::PoolRates` (r:1 w:0)
+	/// Storage: `TestAssets::NextAssetId` (r:1 w:0)
+	/// Storage: `TestAssets::AssetTotals` (r:6 w:6)
+	/// Storage: `StableAsset::Oracles` (r:1 w:1)
+	/// Storage: `StableAsset::EmaHalfLives` (r:1 w:0)
+	/// Storage: `StableAsset::OracleObservations` (r:0 w:1)
+	/// The range of component `u` is `[2, 5]`.
+	fn redeem_multi(u: u32, ) -> Weight {
+		// Minimum execution time: 98_346_000 picoseconds.
+		Weight::from_parts(59_408_460, 0)
+			// Standard Error: 283_480
+			.saturating_add(Weight::from_parts(20_389_440, 0).saturating_mul(u.into()))
+			.saturating_add(RocksDbWeight::get().reads(13_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(u.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(u.into())))
+	}
+	/// The range of component `u` is `[2, 5]`.
+	fn newton_iteration(u: u32, ) -> Weight {
+		// Minimum execution time: 994_000 picoseconds.
+		Weight::from_parts(632_930, 0)
+			// Standard Error: 2_096
+			.saturating_add(Weight::from_parts(195_570, 0).saturating_mul(u.into()))
 	}
 }